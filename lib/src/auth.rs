@@ -0,0 +1,178 @@
+use crate::errors::Result;
+use crate::types::{BoltMap, BoltType};
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::fmt;
+
+/// The credentials a connection authenticates with, see [`crate::ConfigBuilder::auth`]
+#[derive(Clone, PartialEq)]
+pub struct AuthToken {
+    data: BoltMap,
+}
+
+impl AuthToken {
+    /// A user name and password
+    pub fn basic(user: &str, password: &str) -> AuthToken {
+        AuthToken::new("basic")
+            .with("principal", user)
+            .with("credentials", password)
+    }
+
+    /// A token issued by a single sign-on provider, e.g. a JWT
+    pub fn bearer(token: &str) -> AuthToken {
+        AuthToken::new("bearer").with("credentials", token)
+    }
+
+    /// A base64 encoded kerberos ticket
+    pub fn kerberos(ticket: &str) -> AuthToken {
+        AuthToken::new("kerberos")
+            .with("principal", "")
+            .with("credentials", ticket)
+    }
+
+    /// No credentials, for servers with authentication disabled
+    pub fn none() -> AuthToken {
+        AuthToken::new("none")
+    }
+
+    /// Credentials for a custom authentication plugin of the server, the realm and parameters
+    /// are only sent when they are given
+    pub fn custom(
+        scheme: &str,
+        principal: &str,
+        credentials: &str,
+        realm: Option<&str>,
+        parameters: HashMap<String, String>,
+    ) -> AuthToken {
+        let mut token = AuthToken::new(scheme)
+            .with("principal", principal)
+            .with("credentials", credentials);
+        if let Some(realm) = realm {
+            token = token.with("realm", realm);
+        }
+        if !parameters.is_empty() {
+            let mut map = BoltMap::default();
+            for (key, value) in parameters {
+                map.put(key.into(), value.into());
+            }
+            token.data.put("parameters".into(), BoltType::Map(map));
+        }
+        token
+    }
+
+    fn new(scheme: &str) -> AuthToken {
+        let mut data = BoltMap::default();
+        data.put("scheme".into(), scheme.into());
+        AuthToken { data }
+    }
+
+    fn with(mut self, key: &str, value: &str) -> AuthToken {
+        self.data.put(key.into(), value.into());
+        self
+    }
+
+    /// The entries sent with HELLO, or LOGON from bolt 5.1
+    pub(crate) fn to_map(&self) -> BoltMap {
+        self.data.clone()
+    }
+
+    fn get(&self, key: &str) -> Option<String> {
+        self.data.get(key)
+    }
+}
+
+/// Provides the credentials of connections and replaces them when they expire, see
+/// [`crate::ConfigBuilder::auth_manager`].
+///
+/// The token is requested whenever a connection is created or taken from the pool, so
+/// [`AuthTokenManager::get_token`] should return a cached token until it is about to expire.
+/// Pooled connections whose token differs from the current one authenticate again with LOGOFF
+/// and LOGON on bolt 5.1 or later, and are replaced by new connections on older versions.
+#[async_trait]
+pub trait AuthTokenManager: Send + Sync {
+    /// The token to authenticate with
+    async fn get_token(&self) -> Result<AuthToken>;
+
+    /// Called when the server rejected `token` because it expired, the next calls of
+    /// [`AuthTokenManager::get_token`] should return a new token
+    async fn on_token_expired(&self, token: &AuthToken);
+}
+
+/// A token which never changes
+#[async_trait]
+impl AuthTokenManager for AuthToken {
+    async fn get_token(&self) -> Result<AuthToken> {
+        Ok(self.clone())
+    }
+
+    async fn on_token_expired(&self, _token: &AuthToken) {}
+}
+
+impl fmt::Debug for dyn AuthTokenManager {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("AuthTokenManager")
+    }
+}
+
+/// Leaves out the credentials, so that configs can be logged
+impl fmt::Debug for AuthToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AuthToken")
+            .field("scheme", &self.get("scheme").unwrap_or_default())
+            .field("principal", &self.get("principal"))
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_build_auth_tokens() {
+        let basic = AuthToken::basic("neo4j", "secret").to_map();
+        assert_eq!(basic.get::<String>("scheme").unwrap(), "basic");
+        assert_eq!(basic.get::<String>("principal").unwrap(), "neo4j");
+        assert_eq!(basic.get::<String>("credentials").unwrap(), "secret");
+
+        let bearer = AuthToken::bearer("jwt").to_map();
+        assert_eq!(bearer.get::<String>("scheme").unwrap(), "bearer");
+        assert_eq!(bearer.get::<String>("credentials").unwrap(), "jwt");
+        assert_eq!(bearer.value.len(), 2);
+
+        let kerberos = AuthToken::kerberos("dGlja2V0").to_map();
+        assert_eq!(kerberos.get::<String>("scheme").unwrap(), "kerberos");
+        assert_eq!(kerberos.get::<String>("principal").unwrap(), "");
+        assert_eq!(kerberos.get::<String>("credentials").unwrap(), "dGlja2V0");
+
+        let none = AuthToken::none().to_map();
+        assert_eq!(none.get::<String>("scheme").unwrap(), "none");
+        assert_eq!(none.value.len(), 1);
+    }
+
+    #[test]
+    fn should_build_custom_auth_tokens() {
+        let mut parameters = HashMap::new();
+        parameters.insert("tenant".to_owned(), "acme".to_owned());
+
+        let custom =
+            AuthToken::custom("plugin", "alice", "pass", Some("ldap"), parameters).to_map();
+
+        assert_eq!(custom.get::<String>("scheme").unwrap(), "plugin");
+        assert_eq!(custom.get::<String>("principal").unwrap(), "alice");
+        assert_eq!(custom.get::<String>("credentials").unwrap(), "pass");
+        assert_eq!(custom.get::<String>("realm").unwrap(), "ldap");
+        let parameters: BoltMap = custom.get("parameters").unwrap();
+        assert_eq!(parameters.get::<String>("tenant").unwrap(), "acme");
+
+        let custom = AuthToken::custom("plugin", "alice", "pass", None, HashMap::new()).to_map();
+        assert_eq!(custom.value.len(), 3);
+    }
+
+    #[test]
+    fn should_not_show_credentials() {
+        let debug = format!("{:?}", AuthToken::basic("neo4j", "secret"));
+        assert!(debug.contains("neo4j"));
+        assert!(!debug.contains("secret"));
+    }
+}
@@ -0,0 +1,164 @@
+use std::sync::{Arc, Mutex};
+
+/// Chains bookmarks between transactions for causal consistency, see
+/// [`crate::ConfigBuilder::bookmark_manager`].
+///
+/// Every transaction or auto-commit query starts from the bookmarks the manager holds, so it
+/// sees the changes of everything committed through the manager before, even on another
+/// connection or cluster member. Once it commits, its bookmark replaces the ones it started
+/// from. Clones share the same bookmarks.
+#[derive(Debug, Clone, Default)]
+pub struct BookmarkManager {
+    bookmarks: Arc<Mutex<Vec<String>>>,
+    parent: Option<Box<BookmarkManager>>,
+}
+
+impl BookmarkManager {
+    pub fn new() -> BookmarkManager {
+        BookmarkManager::default()
+    }
+
+    /// A manager starting from bookmarks obtained elsewhere, e.g. from another process
+    pub fn with_bookmarks(bookmarks: Vec<String>) -> BookmarkManager {
+        BookmarkManager {
+            bookmarks: Arc::new(Mutex::new(bookmarks)),
+            parent: None,
+        }
+    }
+
+    /// The bookmarks of a session, which also starts from and updates the manager of its graph
+    pub(crate) fn for_session(
+        parent: Option<BookmarkManager>,
+        bookmarks: Vec<String>,
+    ) -> BookmarkManager {
+        BookmarkManager {
+            bookmarks: Arc::new(Mutex::new(bookmarks)),
+            parent: parent.map(Box::new),
+        }
+    }
+
+    pub fn bookmarks(&self) -> Vec<String> {
+        let mut bookmarks = self.own_bookmarks();
+        if let Some(parent) = &self.parent {
+            for bookmark in parent.bookmarks() {
+                if !bookmarks.contains(&bookmark) {
+                    bookmarks.push(bookmark);
+                }
+            }
+        }
+        bookmarks
+    }
+
+    /// The bookmarks of this manager, without those of the manager of the graph
+    pub(crate) fn own_bookmarks(&self) -> Vec<String> {
+        self.bookmarks.lock().unwrap().clone()
+    }
+
+    /// Replaces the bookmarks a unit of work started from by the bookmark it committed, bookmarks
+    /// added concurrently by other units of work are kept
+    pub fn update(&self, previous: &[String], new: Option<String>) {
+        if let Some(parent) = &self.parent {
+            parent.update(previous, new.clone());
+        }
+        if let Some(new) = new {
+            let mut bookmarks = self.bookmarks.lock().unwrap();
+            bookmarks.retain(|bookmark| !previous.contains(bookmark));
+            if !bookmarks.contains(&new) {
+                bookmarks.push(new);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::connection::tests::{recording_stub_server, request_bytes, success, success_with};
+    use crate::messages::BoltRequest;
+    use crate::types::BoltMap;
+    use crate::{config, query, Graph};
+    use bytes::Bytes;
+
+    fn bookmark(bookmark: &str) -> Bytes {
+        let mut metadata = BoltMap::default();
+        metadata.put("bookmark".into(), bookmark.into());
+        success_with(metadata)
+    }
+
+    fn contains(request: &Bytes, bookmark: &str) -> bool {
+        request
+            .windows(bookmark.len())
+            .any(|window| window == bookmark.as_bytes())
+    }
+
+    #[test]
+    fn should_chain_bookmarks() {
+        let manager = BookmarkManager::with_bookmarks(vec!["b0".into()]);
+        let first = manager.bookmarks();
+        let second = manager.bookmarks();
+
+        manager.update(&first, Some("b1".into()));
+        assert_eq!(manager.bookmarks(), ["b1"]);
+        manager.update(&second, Some("b2".into()));
+        assert_eq!(manager.bookmarks(), ["b1", "b2"]);
+        manager.update(&manager.bookmarks(), None);
+        assert_eq!(manager.bookmarks(), ["b1", "b2"]);
+
+        let shared = manager.clone();
+        shared.update(&["b1".to_owned(), "b2".to_owned()], Some("b3".into()));
+        assert_eq!(manager.bookmarks(), ["b3"]);
+    }
+
+    #[test]
+    fn should_pass_session_bookmarks_to_the_graph() {
+        let graph = BookmarkManager::with_bookmarks(vec!["g0".into()]);
+        let session = BookmarkManager::for_session(Some(graph.clone()), vec!["s0".into()]);
+        assert_eq!(session.bookmarks(), ["s0", "g0"]);
+
+        session.update(&session.bookmarks(), Some("s1".into()));
+        assert_eq!(session.own_bookmarks(), ["s1"]);
+        assert_eq!(graph.bookmarks(), ["s1"]);
+    }
+
+    #[tokio::test]
+    async fn should_chain_bookmarks_of_a_graph() {
+        let (uri, requests) = recording_stub_server(vec![
+            vec![success()],
+            vec![success()],
+            vec![bookmark("b1")],
+            vec![success()],
+            vec![bookmark("b2")],
+            vec![success()],
+            vec![bookmark("b3")],
+        ])
+        .await;
+        let manager = BookmarkManager::new();
+        let config = config()
+            .uri(&uri)
+            .user("user")
+            .password("pass")
+            .max_connections(1)
+            .bookmark_manager(manager.clone())
+            .build()
+            .unwrap();
+        let graph = Graph::connect(config).await.unwrap();
+
+        graph.run(query("CREATE ()")).await.unwrap();
+        assert_eq!(manager.bookmarks(), ["b1"]);
+
+        let txn = graph.start_txn().await.unwrap();
+        assert_eq!(txn.commit_with_bookmark().await.unwrap().unwrap(), "b2");
+        assert_eq!(manager.bookmarks(), ["b2"]);
+
+        let mut result = graph.execute(query("MATCH (n) RETURN n")).await.unwrap();
+        assert!(result.next().await.unwrap().is_none());
+        assert_eq!(manager.bookmarks(), ["b3"]);
+
+        let requests = requests.lock().unwrap();
+        let mut extra = BoltMap::default();
+        extra.put("bookmarks".into(), vec!["b1"].into());
+        assert_eq!(requests[3], request_bytes(BoltRequest::begin(extra)));
+        assert!(!contains(&requests[1], "b1"));
+        assert!(contains(&requests[5], "b2"));
+    }
+}
@@ -1,17 +1,38 @@
+use crate::auth::{AuthToken, AuthTokenManager};
+use crate::bookmarks::BookmarkManager;
 pub use crate::errors::*;
+use crate::transport::TlsConfig;
+use crate::uri::Uri;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
 
 const DEFAULT_FETCH_SIZE: usize = 200;
 const DEFAULT_MAX_CONNECTIONS: usize = 16;
+const DEFAULT_MAX_RETRY_TIME: Duration = Duration::from_secs(30);
+const DEFAULT_MAX_CONNECTION_LIFETIME: Duration = Duration::from_secs(60 * 60);
+const DEFAULT_CONNECTION_ACQUISITION_TIMEOUT: Duration = Duration::from_secs(60);
+pub(crate) const DEFAULT_CONNECT_TIMEOUT: Duration = Duration::from_secs(30);
+const DEFAULT_MIN_IDLE: usize = 1;
 
 /// The configuration used to connect to the database, see [`crate::Graph::connect`]
 #[derive(Debug, Clone)]
 pub struct Config {
     pub(crate) uri: String,
-    pub(crate) user: String,
-    pub(crate) password: String,
+    pub(crate) auth: Arc<dyn AuthTokenManager>,
     pub(crate) max_connections: usize,
     pub(crate) db: String,
     pub(crate) fetch_size: usize,
+    pub(crate) tls: TlsConfig,
+    pub(crate) max_retry_time: Duration,
+    pub(crate) max_connection_lifetime: Duration,
+    pub(crate) idle_time_before_connection_test: Option<Duration>,
+    pub(crate) max_connection_idle_time: Option<Duration>,
+    pub(crate) connection_acquisition_timeout: Duration,
+    pub(crate) connect_timeout: Duration,
+    pub(crate) min_idle: usize,
+    pub(crate) bookmark_manager: Option<BookmarkManager>,
+    pub(crate) imp_user: Option<String>,
 }
 
 /// A builder to override default configurations and build the [`Config`]
@@ -19,13 +40,26 @@ pub struct ConfigBuilder {
     uri: Option<String>,
     user: Option<String>,
     password: Option<String>,
+    auth: Option<Arc<dyn AuthTokenManager>>,
     db: Option<String>,
     fetch_size: Option<usize>,
     max_connections: Option<usize>,
+    tls: TlsConfig,
+    max_retry_time: Duration,
+    max_connection_lifetime: Duration,
+    idle_time_before_connection_test: Option<Duration>,
+    max_connection_idle_time: Option<Duration>,
+    connection_acquisition_timeout: Duration,
+    connect_timeout: Duration,
+    min_idle: usize,
+    bookmark_manager: Option<BookmarkManager>,
 }
 
 impl ConfigBuilder {
-    ///the uri of the neo4j server
+    ///the uri of the neo4j server, e.g. `neo4j+s://xxx.databases.neo4j.io` or `localhost:7687`.
+    ///supported schemes are `bolt`, `bolt+s`, `bolt+ssc`, `neo4j`, `neo4j+s` and `neo4j+ssc`,
+    ///`+s` requires a certificate signed by a trusted CA and `+ssc` also accepts self-signed
+    ///certificates
     pub fn uri(mut self, uri: &str) -> Self {
         self.uri = Some(uri.to_owned());
         self
@@ -43,6 +77,21 @@ impl ConfigBuilder {
         self
     }
 
+    ///the credentials to authenticate with instead of [`ConfigBuilder::user`] and
+    ///[`ConfigBuilder::password`], e.g. a bearer token or [`AuthToken::none`] for servers with
+    ///authentication disabled
+    pub fn auth(mut self, auth: AuthToken) -> Self {
+        self.auth = Some(Arc::new(auth));
+        self
+    }
+
+    ///asks `manager` for the credentials of new connections and tells it when the server
+    ///rejected them because they expired, for tokens which have to be refreshed
+    pub fn auth_manager<M: AuthTokenManager + 'static>(mut self, manager: M) -> Self {
+        self.auth = Some(Arc::new(manager));
+        self
+    }
+
     ///the name of the database, defaults to "neo4j" if not configured.
     pub fn db(mut self, db: &str) -> Self {
         self.db = Some(db.to_owned());
@@ -63,24 +112,115 @@ impl ConfigBuilder {
         self
     }
 
+    ///a PEM file with additional CA certificates to trust for `+s` schemes
+    pub fn ca_certificate<P: AsRef<Path>>(mut self, path: P) -> Self {
+        self.tls.ca_certificate = Some(path.as_ref().to_owned());
+        self
+    }
+
+    ///PEM files with the certificate chain and private key presented to servers requiring
+    ///client authentication
+    pub fn client_certificate<C: AsRef<Path>, K: AsRef<Path>>(mut self, cert: C, key: K) -> Self {
+        self.tls.client_certificate = Some((cert.as_ref().to_owned(), key.as_ref().to_owned()));
+        self
+    }
+
+    ///how long transaction functions like [`crate::Graph::execute_write`] keep retrying
+    ///transactions which failed with a retryable error, default is 30 seconds
+    pub fn max_retry_time(mut self, max_retry_time: Duration) -> Self {
+        self.max_retry_time = max_retry_time;
+        self
+    }
+
+    ///connections older than this are closed instead of being reused, so that the load is spread
+    ///over new cluster members and connections are renewed before a proxy or firewall cuts them,
+    ///default is 1 hour
+    pub fn max_connection_lifetime(mut self, max_connection_lifetime: Duration) -> Self {
+        self.max_connection_lifetime = max_connection_lifetime;
+        self
+    }
+
+    ///connections idle for longer than this are tested with a round trip to the server before
+    ///they are handed out, and replaced if the server doesn't answer. Not tested by default
+    pub fn idle_time_before_connection_test(mut self, idle_time: Duration) -> Self {
+        self.idle_time_before_connection_test = Some(idle_time);
+        self
+    }
+
+    ///connections idle for longer than this are closed by a background task, so that connections
+    ///killed silently by a firewall are never handed out. Idle connections are kept by default
+    pub fn max_connection_idle_time(mut self, idle_time: Duration) -> Self {
+        self.max_connection_idle_time = Some(idle_time);
+        self
+    }
+
+    ///how long queries and transactions wait for a connection when all connections of the pool
+    ///are in use, before failing with [`Error::ConnectionAcquisitionTimeout`], default is 60
+    ///seconds
+    pub fn connection_acquisition_timeout(mut self, timeout: Duration) -> Self {
+        self.connection_acquisition_timeout = timeout;
+        self
+    }
+
+    ///how long opening a connection may take before failing with [`Error::ConnectTimeout`]: the
+    ///TCP connection, the TLS and bolt handshakes and the authentication, default is 30 seconds
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = timeout;
+        self
+    }
+
+    ///the number of connections opened by [`crate::Graph::connect`], which fails if they can't
+    ///be opened. Connections closed for being idle are replaced to keep this many open, default
+    ///is 1 and it must not exceed [`ConfigBuilder::max_connections`]. For `neo4j` uris the pool
+    ///of each reader and writer of the database gets this many connections, connecting fails
+    ///unless a reader and a writer can be reached, and 0 connects without fetching the routing
+    ///table
+    pub fn min_idle(mut self, min_idle: usize) -> Self {
+        self.min_idle = min_idle;
+        self
+    }
+
+    ///chains the bookmarks of all transactions and auto-commit queries of the graph, so that each
+    ///of them sees the changes committed before. A manager can be shared by several graphs
+    pub fn bookmark_manager(mut self, bookmark_manager: BookmarkManager) -> Self {
+        self.bookmark_manager = Some(bookmark_manager);
+        self
+    }
+
     pub fn build(self) -> Result<Config> {
+        if let Some(uri) = &self.uri {
+            Uri::parse(uri)?;
+        }
+        let auth = match (self.auth, self.user, self.password) {
+            (Some(auth), _, _) => auth,
+            (None, Some(user), Some(password)) => Arc::new(AuthToken::basic(&user, &password)),
+            _ => return Err(Error::InvalidConfig),
+        };
         if self.uri.is_none()
-            || self.user.is_none()
-            || self.password.is_none()
             || self.fetch_size.is_none()
             || self.max_connections.is_none()
             || self.db.is_none()
+            || Some(self.min_idle) > self.max_connections
         {
             Err(Error::InvalidConfig)
         } else {
             //The config attributes are validated before unwrapping
             Ok(Config {
                 uri: self.uri.unwrap(),
-                user: self.user.unwrap(),
-                password: self.password.unwrap(),
+                auth,
                 fetch_size: self.fetch_size.unwrap(),
                 max_connections: self.max_connections.unwrap(),
                 db: self.db.unwrap(),
+                tls: self.tls,
+                max_retry_time: self.max_retry_time,
+                max_connection_lifetime: self.max_connection_lifetime,
+                idle_time_before_connection_test: self.idle_time_before_connection_test,
+                max_connection_idle_time: self.max_connection_idle_time,
+                connection_acquisition_timeout: self.connection_acquisition_timeout,
+                connect_timeout: self.connect_timeout,
+                min_idle: self.min_idle,
+                bookmark_manager: self.bookmark_manager,
+                imp_user: None,
             })
         }
     }
@@ -92,9 +232,19 @@ pub fn config() -> ConfigBuilder {
         uri: None,
         user: None,
         password: None,
+        auth: None,
         db: Some("".to_owned()),
         max_connections: Some(DEFAULT_MAX_CONNECTIONS),
         fetch_size: Some(DEFAULT_FETCH_SIZE),
+        tls: TlsConfig::default(),
+        max_retry_time: DEFAULT_MAX_RETRY_TIME,
+        max_connection_lifetime: DEFAULT_MAX_CONNECTION_LIFETIME,
+        idle_time_before_connection_test: None,
+        max_connection_idle_time: None,
+        connection_acquisition_timeout: DEFAULT_CONNECTION_ACQUISITION_TIMEOUT,
+        connect_timeout: DEFAULT_CONNECT_TIMEOUT,
+        min_idle: DEFAULT_MIN_IDLE,
+        bookmark_manager: None,
     }
 }
 
@@ -111,14 +261,70 @@ mod tests {
             .db("some_db")
             .fetch_size(10)
             .max_connections(5)
+            .max_retry_time(Duration::from_secs(5))
             .build()
             .unwrap();
         assert_eq!(config.uri, "127.0.0.1:7687");
-        assert_eq!(config.user, "some_user");
-        assert_eq!(config.password, "some_password");
+        assert_eq!(
+            config.auth.get_token().await.unwrap(),
+            AuthToken::basic("some_user", "some_password")
+        );
         assert_eq!(config.db, "some_db");
         assert_eq!(config.fetch_size, 10);
         assert_eq!(config.max_connections, 5);
+        assert_eq!(config.max_retry_time, Duration::from_secs(5));
+    }
+
+    #[tokio::test]
+    async fn should_build_the_pool_config() {
+        let custom = config()
+            .uri("127.0.0.1:7687")
+            .user("some_user")
+            .password("some_password")
+            .max_connection_lifetime(Duration::from_secs(600))
+            .idle_time_before_connection_test(Duration::from_secs(30))
+            .max_connection_idle_time(Duration::from_secs(300))
+            .build()
+            .unwrap();
+        assert_eq!(custom.max_connection_lifetime, Duration::from_secs(600));
+        assert_eq!(
+            custom.idle_time_before_connection_test,
+            Some(Duration::from_secs(30))
+        );
+        assert_eq!(
+            custom.max_connection_idle_time,
+            Some(Duration::from_secs(300))
+        );
+
+        let defaults = config()
+            .uri("127.0.0.1:7687")
+            .user("some_user")
+            .password("some_password")
+            .build()
+            .unwrap();
+        assert_eq!(defaults.max_connection_lifetime, Duration::from_secs(3600));
+        assert_eq!(defaults.idle_time_before_connection_test, None);
+        assert_eq!(defaults.max_connection_idle_time, None);
+        assert_eq!(
+            defaults.connection_acquisition_timeout,
+            Duration::from_secs(60)
+        );
+        assert_eq!(defaults.connect_timeout, Duration::from_secs(30));
+        assert_eq!(defaults.min_idle, 1);
+    }
+
+    #[tokio::test]
+    async fn should_reject_more_idle_than_max_connections() {
+        assert!(matches!(
+            config()
+                .uri("127.0.0.1:7687")
+                .user("some_user")
+                .password("some_password")
+                .max_connections(2)
+                .min_idle(3)
+                .build(),
+            Err(Error::InvalidConfig)
+        ));
     }
 
     #[tokio::test]
@@ -130,11 +336,14 @@ mod tests {
             .build()
             .unwrap();
         assert_eq!(config.uri, "127.0.0.1:7687");
-        assert_eq!(config.user, "some_user");
-        assert_eq!(config.password, "some_password");
+        assert_eq!(
+            config.auth.get_token().await.unwrap(),
+            AuthToken::basic("some_user", "some_password")
+        );
         assert_eq!(config.db, "");
         assert_eq!(config.fetch_size, 200);
         assert_eq!(config.max_connections, 16);
+        assert_eq!(config.max_retry_time, Duration::from_secs(30));
     }
 
     #[tokio::test]
@@ -156,5 +365,54 @@ mod tests {
             .user("some_user")
             .build()
             .is_err());
+
+        assert!(config()
+            .uri("http://127.0.0.1:7687")
+            .user("some_user")
+            .password("some_password")
+            .build()
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn should_build_with_an_auth_token() {
+        let bearer = config()
+            .uri("127.0.0.1:7687")
+            .auth(AuthToken::bearer("token"))
+            .build()
+            .unwrap();
+        assert_eq!(
+            bearer.auth.get_token().await.unwrap(),
+            AuthToken::bearer("token")
+        );
+
+        let none = config()
+            .uri("127.0.0.1:7687")
+            .user("some_user")
+            .password("some_password")
+            .auth(AuthToken::none())
+            .build()
+            .unwrap();
+        assert_eq!(none.auth.get_token().await.unwrap(), AuthToken::none());
+    }
+
+    #[tokio::test]
+    async fn should_build_tls_config() {
+        let config = config()
+            .uri("neo4j+s://localhost:7687")
+            .user("some_user")
+            .password("some_password")
+            .ca_certificate("ca.pem")
+            .client_certificate("client.pem", "client.key")
+            .build()
+            .unwrap();
+        assert_eq!(
+            config.tls.ca_certificate.as_deref(),
+            Some(Path::new("ca.pem"))
+        );
+        assert_eq!(
+            config.tls.client_certificate,
+            Some(("client.pem".into(), "client.key".into()))
+        );
     }
 }
@@ -1,45 +1,321 @@
-use crate::errors::{unexpected, Error, Result};
+use crate::auth::{AuthToken, AuthTokenManager};
+use crate::config::DEFAULT_CONNECT_TIMEOUT;
+use crate::errors::{unexpected, Error, Result, TOKEN_EXPIRED};
 use crate::messages::*;
+use crate::trace::{span, traced, Empty};
+use crate::transport::{Connector, TlsConfig, Transport};
+use crate::types::BoltMap;
+use crate::uri::Uri;
 use crate::version::Version;
 use bytes::*;
+use std::collections::{HashMap, VecDeque};
 use std::mem;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tokio::io::BufStream;
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
-use tokio::net::TcpStream;
 
 const MAX_CHUNK_SIZE: usize = 65_535 - mem::size_of::<u16>();
 
+/// The state of the bolt connection as seen by the server, see
+/// <https://neo4j.com/docs/bolt/current/bolt/server-state/>
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConnectionState {
+    Ready,
+    Streaming,
+    TxReady,
+    TxStreaming,
+    /// A request failed, the server ignores everything until the connection is reset
+    Failed,
+    /// The connection can't be used anymore and must be discarded
+    Defunct,
+}
+
+/// Requests sent to the server whose summary hasn't been received yet
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Pending {
+    Auth,
+    Run,
+    Pull,
+    Discard,
+    Begin,
+    Commit,
+    Rollback,
+    Reset,
+    Route,
+}
+
+impl From<&BoltRequest> for Pending {
+    fn from(request: &BoltRequest) -> Self {
+        match request {
+            BoltRequest::Hello(_) | BoltRequest::Logon(_) | BoltRequest::Logoff(_) => Pending::Auth,
+            BoltRequest::Run(_) => Pending::Run,
+            BoltRequest::Pull(_) => Pending::Pull,
+            BoltRequest::Discard(_) => Pending::Discard,
+            BoltRequest::Begin(_) => Pending::Begin,
+            BoltRequest::Commit(_) => Pending::Commit,
+            BoltRequest::Rollback(_) => Pending::Rollback,
+            BoltRequest::Reset(_) => Pending::Reset,
+            BoltRequest::Route(_) => Pending::Route,
+        }
+    }
+}
+
+/// Who receives the response to a request, requests are pipelined so responses to earlier
+/// requests may still be outstanding when a response is received
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Receiver {
+    /// The next call to [`Connection::recv`]
+    Caller,
+    /// The stream with the query id, see [`Connection::send_for`]
+    Stream(i64),
+    /// Nobody unless it fails, see [`Connection::send_deferred`]
+    Deferred,
+}
+
+/// Where and how to connect, shared by all connections of a pool
+#[derive(Debug, Clone)]
+pub struct ConnectionInfo {
+    pub(crate) uri: Uri,
+    pub(crate) auth: Arc<dyn AuthTokenManager>,
+    pub(crate) connect_timeout: Duration,
+    connector: Connector,
+    routing: Option<BoltMap>,
+}
+
+impl ConnectionInfo {
+    pub fn new(
+        uri: &str,
+        auth: Arc<dyn AuthTokenManager>,
+        tls: &TlsConfig,
+    ) -> Result<ConnectionInfo> {
+        let uri = Uri::parse(uri)?;
+        let connector = Connector::new(&uri, tls)?;
+        let routing = if uri.routing {
+            let mut routing = BoltMap::default();
+            routing.put("address".into(), uri.address().into());
+            for (key, value) in &uri.routing_context {
+                routing.put(key.as_str().into(), value.as_str().into());
+            }
+            Some(routing)
+        } else {
+            None
+        };
+        Ok(ConnectionInfo {
+            uri,
+            auth,
+            connect_timeout: DEFAULT_CONNECT_TIMEOUT,
+            connector,
+            routing,
+        })
+    }
+
+    /// The same settings for another member of the cluster
+    pub fn with_address(&self, address: &str) -> Result<ConnectionInfo> {
+        let (host, port) = Uri::split_host_port(address)
+            .ok_or_else(|| Error::InvalidUri(format!("invalid server address {}", address)))?;
+        let mut info = self.clone();
+        info.uri.host = host;
+        info.uri.port = port;
+        Ok(info)
+    }
+
+    pub fn address(&self) -> String {
+        self.uri.address()
+    }
+
+    /// The routing context sent with HELLO and ROUTE, `None` unless the uri has a `neo4j` scheme
+    pub fn routing_context(&self) -> Option<&BoltMap> {
+        self.routing.as_ref()
+    }
+}
+
 #[derive(Debug)]
 pub struct Connection {
     version: Version,
-    stream: BufStream<TcpStream>,
+    stream: BufStream<Transport>,
+    state: ConnectionState,
+    pending: VecDeque<(Pending, Receiver)>,
+    open_results: usize,
+    /// Responses received for streams while another request was waiting for its response
+    results: HashMap<i64, VecDeque<BoltResponse>>,
+    /// The failure of a deferred request, reported instead of the responses it made the server
+    /// ignore
+    deferred_error: Option<Error>,
+    /// The token the connection authenticated with
+    auth: AuthToken,
+    /// Whether the server rejected a request because the token expired
+    token_expired: bool,
+    created: Instant,
+    last_used: Instant,
 }
 
 impl Connection {
-    pub async fn new(uri: &str, user: &str, password: &str) -> Result<Connection> {
-        let mut stream = BufStream::new(TcpStream::connect(uri).await?);
+    /// Opens a connection and logs in, failing with [`Error::ConnectTimeout`] if the TCP
+    /// connection, the TLS and bolt handshakes and the authentication together take longer than
+    /// the connect timeout
+    pub async fn new(info: &ConnectionInfo) -> Result<Connection> {
+        tokio::time::timeout(info.connect_timeout, Self::open(info))
+            .await
+            .map_err(|_| Error::ConnectTimeout(info.address()))?
+    }
+
+    async fn open(info: &ConnectionInfo) -> Result<Connection> {
+        let (host, port) = (info.uri.host.as_str(), info.uri.port);
+        let connect = info.connector.connect(host, port);
+        let span = span!("connect", server.address = host, server.port = port);
+        let mut stream = BufStream::new(traced(span, connect).await?);
+        let span = span!(
+            "handshake",
+            server.address = host,
+            server.port = port,
+            neo4j.bolt.version = Empty,
+        );
+        let version = traced(span.clone(), Self::handshake(&mut stream)).await?;
+        span.record("neo4j.bolt.version", version.to_string().as_str());
+        let auth = info.auth.get_token().await?;
+        let mut connection = Connection {
+            version,
+            stream,
+            state: ConnectionState::Ready,
+            pending: VecDeque::new(),
+            open_results: 0,
+            results: HashMap::new(),
+            deferred_error: None,
+            auth: auth.clone(),
+            token_expired: false,
+            created: Instant::now(),
+            last_used: Instant::now(),
+        };
+        let span = span!(
+            "HELLO",
+            db.operation.name = "HELLO",
+            server.address = host,
+            server.port = port,
+        );
+        let result = traced(span, connection.login(info.routing.clone())).await;
+        if connection.token_expired {
+            info.auth.on_token_expired(&auth).await;
+        }
+        result.map(|_| connection)
+    }
+
+    /// Negotiates the bolt version with the server
+    async fn handshake(stream: &mut BufStream<Transport>) -> Result<Version> {
         stream.write_all(&[0x60, 0x60, 0xB0, 0x17]).await?;
         stream.write_all(&Version::supported_versions()).await?;
         stream.flush().await?;
         let mut response = [0, 0, 0, 0];
         stream.read_exact(&mut response).await?;
-        let version = Version::parse(response)?;
-        let mut connection = Connection { version, stream };
-        let hello = BoltRequest::hello("neo4rs", user.to_owned(), password.to_owned());
-        match connection.send_recv(hello).await? {
-            BoltResponse::Success(_msg) => Ok(connection),
+        Version::parse(response)
+    }
+
+    async fn login(&mut self, routing: Option<BoltMap>) -> Result<()> {
+        let auth = self.auth.to_map();
+        let hello = BoltRequest::hello(self.version, "neo4rs", auth.clone(), routing);
+        let success = self.authenticate(hello, "HELLO").await?;
+        let patches: Vec<String> = success.get("patch_bolt").unwrap_or_default();
+        if patches.iter().any(|patch| patch == "utc") {
+            self.version = self.version.with_utc_patch();
+        }
+        if self.version.supports_logon() {
+            self.authenticate(BoltRequest::logon(auth), "LOGON").await?;
+        }
+        Ok(())
+    }
+
+    /// Authenticates again with another token on bolt 5.1 or later, sending LOGOFF and LOGON
+    /// without waiting in between. The connection keeps its token unless LOGON succeeds.
+    pub async fn reauthenticate(&mut self, auth: AuthToken) -> Result<()> {
+        self.send(BoltRequest::logoff()).await?;
+        self.send(BoltRequest::logon(auth.to_map())).await?;
+        self.token_expired = false;
+        self.recv_auth("LOGOFF").await?;
+        self.recv_auth("LOGON").await?;
+        self.auth = auth;
+        Ok(())
+    }
+
+    async fn authenticate(&mut self, request: BoltRequest, name: &str) -> Result<Success> {
+        self.send(request).await?;
+        self.recv_auth(name).await
+    }
+
+    async fn recv_auth(&mut self, name: &str) -> Result<Success> {
+        match self.recv().await? {
+            BoltResponse::Success(success) => Ok(success),
+            BoltResponse::Failure(msg) if self.token_expired => Err(msg.into_error()),
             BoltResponse::Failure(msg) => {
                 Err(Error::AuthenticationError(msg.get("message").unwrap()))
             }
 
-            msg => Err(unexpected(msg, "HELLO")),
+            msg => Err(unexpected(msg, name)),
         }
     }
 
+    /// The token the connection authenticated with
+    pub fn auth_token(&self) -> &AuthToken {
+        &self.auth
+    }
+
+    /// Whether the server rejected a request because the token of the connection expired
+    pub fn is_token_expired(&self) -> bool {
+        self.token_expired
+    }
+
+    pub fn version(&self) -> Version {
+        self.version
+    }
+
+    pub fn state(&self) -> ConnectionState {
+        self.state
+    }
+
+    pub fn is_failed(&self) -> bool {
+        self.state == ConnectionState::Failed
+    }
+
+    pub fn is_defunct(&self) -> bool {
+        self.state == ConnectionState::Defunct
+    }
+
+    /// Whether the connection can be used right away: ready and without responses outstanding
+    pub fn is_clean(&self) -> bool {
+        self.state == ConnectionState::Ready
+            && self.pending.is_empty()
+            && self.results.is_empty()
+            && self.deferred_error.is_none()
+    }
+
+    /// The time since the connection was opened
+    pub fn age(&self) -> Duration {
+        self.created.elapsed()
+    }
+
+    /// The time since the last message was received from the server
+    pub fn idle_time(&self) -> Duration {
+        self.last_used.elapsed()
+    }
+
+    /// Brings the connection back to the ready state, any transaction in progress is rolled back
+    /// by the server and responses to requests sent earlier are discarded. The connection is
+    /// marked as defunct if the reset doesn't succeed.
     pub async fn reset(&mut self) -> Result<()> {
-        match self.send_recv(BoltRequest::reset()).await? {
-            BoltResponse::Success(_) => Ok(()),
-            msg => Err(unexpected(msg, "RESET")),
+        self.send(BoltRequest::reset()).await?;
+        self.results.clear();
+        self.deferred_error = None;
+        loop {
+            match self.receive().await {
+                Ok(BoltResponse::Success(_)) if self.pending.is_empty() => return Ok(()),
+                Ok(BoltResponse::Failure(failure)) if self.pending.is_empty() => {
+                    return Err(failure.into_error())
+                }
+                Ok(_) => continue,
+                Err(e) => {
+                    self.state = ConnectionState::Defunct;
+                    return Err(e);
+                }
+            }
         }
     }
 
@@ -48,9 +324,182 @@ impl Connection {
         self.recv().await
     }
 
+    /// Sends a request to the server.
+    ///
+    /// Requests other than RESET are not sent while the connection is failed, as the server
+    /// would ignore them anyway.
     pub async fn send(&mut self, message: BoltRequest) -> Result<()> {
-        let end_marker: [u8; 2] = [0, 0];
+        self.send_to(message, Receiver::Caller).await
+    }
+
+    /// Sends a PULL or DISCARD for the stream with the query id `qid`, whose responses are
+    /// received with [`Connection::recv_for`]. A PULL pipelined with its RUN is sent for the
+    /// query id `-1` until [`Connection::assign_qid`] is called.
+    pub async fn send_for(&mut self, qid: i64, message: BoltRequest) -> Result<()> {
+        self.send_to(message, Receiver::Stream(qid)).await
+    }
+
+    /// Sends a request, like BEGIN, without waiting for its response. The response is read
+    /// before the responses to later requests, its failure is reported in place of them.
+    pub async fn send_deferred(&mut self, message: BoltRequest) -> Result<()> {
+        self.send_to(message, Receiver::Deferred).await
+    }
+
+    /// Passes the responses to requests sent for the query id `-1` to the stream `qid`, once the
+    /// RUN they were pipelined with returned it
+    pub fn assign_qid(&mut self, qid: i64) {
+        for (_, receiver) in self.pending.iter_mut() {
+            if *receiver == Receiver::Stream(-1) {
+                *receiver = Receiver::Stream(qid);
+            }
+        }
+    }
+
+    /// Reads the responses to all requests sent before, the responses of streams are buffered
+    pub async fn catch_up(&mut self) -> Result<()> {
+        self.buffer_results(None).await
+    }
+
+    async fn send_to(&mut self, message: BoltRequest, receiver: Receiver) -> Result<()> {
+        let pending = Pending::from(&message);
+        match self.state {
+            ConnectionState::Defunct => return Err(Error::ConnectionError),
+            ConnectionState::Failed if pending != Pending::Reset => {
+                return Err(self.deferred_error.clone().unwrap_or(Error::Ignored))
+            }
+            _ => {}
+        }
+
         let bytes: Bytes = message.into_bytes(self.version)?;
+        if let Err(e) = self.write_chunks(bytes).await {
+            self.state = ConnectionState::Defunct;
+            return Err(e);
+        }
+        self.pending.push_back((pending, receiver));
+        Ok(())
+    }
+
+    /// Receives the next response from the server.
+    ///
+    /// Responses to the requests of streams sent before are buffered until the streams ask for
+    /// them. An `IGNORED` response is reported as [`Error::Ignored`].
+    pub async fn recv(&mut self) -> Result<BoltResponse> {
+        self.buffer_results(None).await?;
+        let response = self.receive().await?;
+        self.ignored_as_error(response)
+    }
+
+    /// Receives the next response to a request sent with [`Connection::send_for`] for the stream
+    /// `qid`, responses to the requests of other streams sent before are buffered.
+    pub async fn recv_for(&mut self, qid: i64) -> Result<BoltResponse> {
+        if let Some(response) = self.results.get_mut(&qid).and_then(VecDeque::pop_front) {
+            return self.ignored_as_error(response);
+        }
+        self.buffer_results(Some(qid)).await?;
+        let response = self.receive().await?;
+        self.ignored_as_error(response)
+    }
+
+    /// Reads the responses to deferred requests and to the requests of streams other than `qid`
+    /// which were sent before the next request
+    async fn buffer_results(&mut self, qid: Option<i64>) -> Result<()> {
+        while let Some(&(_, receiver)) = self.pending.front() {
+            match receiver {
+                Receiver::Stream(owner) if Some(owner) != qid => {
+                    let response = self.receive().await?;
+                    self.results.entry(owner).or_default().push_back(response);
+                }
+                Receiver::Deferred => {
+                    if let BoltResponse::Failure(failure) = self.receive().await? {
+                        self.deferred_error = Some(failure.into_error());
+                    }
+                }
+                _ => break,
+            }
+        }
+        Ok(())
+    }
+
+    /// An `IGNORED` response is reported as the failure of a deferred request which caused it,
+    /// or as [`Error::Ignored`]
+    fn ignored_as_error(&self, response: BoltResponse) -> Result<BoltResponse> {
+        match response {
+            BoltResponse::Ignored(_) => Err(self.deferred_error.clone().unwrap_or(Error::Ignored)),
+            response => Ok(response),
+        }
+    }
+
+    /// Reads the next response and updates the state of the connection
+    async fn receive(&mut self) -> Result<BoltResponse> {
+        let response = match self.read_message().await {
+            Ok(response) => response,
+            Err(e) => {
+                self.state = ConnectionState::Defunct;
+                return Err(e);
+            }
+        };
+        self.last_used = Instant::now();
+
+        match &response {
+            BoltResponse::Record(_) => {}
+            BoltResponse::Success(success) => {
+                let has_more = success.get("has_more").unwrap_or(false);
+                self.on_success(has_more);
+            }
+            BoltResponse::Failure(failure) => {
+                if failure.get::<String>("code").as_deref() == Some(TOKEN_EXPIRED) {
+                    self.token_expired = true;
+                }
+                self.on_failure()
+            }
+            BoltResponse::Ignored(_) => {
+                self.pending.pop_front();
+            }
+        }
+        Ok(response)
+    }
+
+    fn on_success(&mut self, has_more: bool) {
+        use ConnectionState::*;
+        let pending = self.pending.pop_front().map(|(pending, _)| pending);
+        self.state = match (pending, self.state) {
+            (Some(Pending::Auth), _) => Ready,
+            (Some(Pending::Run), Ready) => {
+                self.open_results = 1;
+                Streaming
+            }
+            (Some(Pending::Run), TxReady) | (Some(Pending::Run), TxStreaming) => {
+                self.open_results += 1;
+                TxStreaming
+            }
+            (Some(Pending::Pull), state) | (Some(Pending::Discard), state) if !has_more => {
+                self.open_results = self.open_results.saturating_sub(1);
+                match state {
+                    Streaming => Ready,
+                    TxStreaming if self.open_results == 0 => TxReady,
+                    state => state,
+                }
+            }
+            (Some(Pending::Begin), _) => TxReady,
+            (Some(Pending::Commit), _)
+            | (Some(Pending::Rollback), _)
+            | (Some(Pending::Reset), _) => {
+                self.open_results = 0;
+                Ready
+            }
+            (_, state) => state,
+        }
+    }
+
+    fn on_failure(&mut self) {
+        self.state = match self.pending.pop_front().map(|(pending, _)| pending) {
+            Some(Pending::Auth) | Some(Pending::Reset) => ConnectionState::Defunct,
+            _ => ConnectionState::Failed,
+        };
+    }
+
+    async fn write_chunks(&mut self, bytes: Bytes) -> Result<()> {
+        let end_marker: [u8; 2] = [0, 0];
         for c in bytes.chunks(MAX_CHUNK_SIZE) {
             self.stream.write_u16(c.len() as u16).await?;
             self.stream.write_all(c).await?;
@@ -60,7 +509,7 @@ impl Connection {
         Ok(())
     }
 
-    pub async fn recv(&mut self) -> Result<BoltResponse> {
+    async fn read_message(&mut self) -> Result<BoltResponse> {
         let mut bytes = BytesMut::new();
         let mut chunk_size = 0;
         while chunk_size == 0 {
@@ -88,3 +537,661 @@ impl Connection {
         Ok(u16::from_be_bytes(data))
     }
 }
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::types::{BoltList, BoltType};
+    use tokio::io::{AsyncRead, AsyncWrite};
+    use tokio::net::TcpListener;
+
+    pub(crate) fn success() -> Bytes {
+        Bytes::from_static(&[0xB1, 0x70, 0xA0])
+    }
+
+    pub(crate) fn success_with(metadata: BoltMap) -> Bytes {
+        let mut bytes = BytesMut::from(&[0xB1, 0x70][..]);
+        bytes.put(metadata.into_bytes(Version::V4_4).unwrap());
+        bytes.freeze()
+    }
+
+    pub(crate) fn failure(code: &str) -> Bytes {
+        let mut metadata = BoltMap::default();
+        metadata.put("code".into(), code.into());
+        metadata.put("message".into(), "failed".into());
+        let mut bytes = BytesMut::from(&[0xB1, 0x7F][..]);
+        bytes.put(metadata.into_bytes(Version::V4_4).unwrap());
+        bytes.freeze()
+    }
+
+    pub(crate) fn record(values: BoltList) -> Bytes {
+        let mut bytes = BytesMut::from(&[0xB1, 0x71][..]);
+        bytes.put(values.into_bytes(Version::V4_4).unwrap());
+        bytes.freeze()
+    }
+
+    pub(crate) fn ignored() -> Bytes {
+        Bytes::from_static(&[0xB0, 0x7E])
+    }
+
+    /// The requests received by a stub server, in the order they arrived
+    pub(crate) type Requests = Arc<std::sync::Mutex<Vec<Bytes>>>;
+
+    /// Accepts a single bolt 4.4 connection and answers the n-th request with the n-th batch of
+    /// responses, an empty batch means the request gets no response.
+    pub(crate) async fn stub_server(script: Vec<Vec<Bytes>>) -> String {
+        recording_stub_server(script).await.0
+    }
+
+    /// Like [`stub_server`], also returns the requests the server receives
+    pub(crate) async fn recording_stub_server(script: Vec<Vec<Bytes>>) -> (String, Requests) {
+        versioned_stub_server([0, 0, 4, 4], script).await
+    }
+
+    /// Like [`recording_stub_server`], negotiating the given bolt version
+    pub(crate) async fn versioned_stub_server(
+        version: [u8; 4],
+        script: Vec<Vec<Bytes>>,
+    ) -> (String, Requests) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        let requests = Requests::default();
+        let received = requests.clone();
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            serve_recording(stream, version, script, received).await;
+        });
+        (address, requests)
+    }
+
+    /// The bytes a request is sent as, to compare with [`Requests`]
+    pub(crate) fn request_bytes(request: BoltRequest) -> Bytes {
+        request.into_bytes(Version::V4_4).unwrap()
+    }
+
+    pub(crate) async fn serve<S: AsyncRead + AsyncWrite + Unpin>(
+        stream: S,
+        script: Vec<Vec<Bytes>>,
+    ) {
+        serve_recording(stream, [0, 0, 4, 4], script, Requests::default()).await
+    }
+
+    async fn serve_recording<S: AsyncRead + AsyncWrite + Unpin>(
+        stream: S,
+        version: [u8; 4],
+        script: Vec<Vec<Bytes>>,
+        requests: Requests,
+    ) {
+        let mut stream = BufStream::new(stream);
+        let mut handshake = [0; 20];
+        stream.read_exact(&mut handshake).await.unwrap();
+        stream.write_all(&version).await.unwrap();
+        stream.flush().await.unwrap();
+        for responses in script {
+            let mut request = BytesMut::new();
+            loop {
+                let size = stream.read_u16().await.unwrap();
+                if size == 0 {
+                    break;
+                }
+                let mut chunk = vec![0; size as usize];
+                stream.read_exact(&mut chunk).await.unwrap();
+                request.put_slice(&chunk);
+            }
+            requests.lock().unwrap().push(request.freeze());
+            for response in responses {
+                stream.write_u16(response.len() as u16).await.unwrap();
+                stream.write_all(&response).await.unwrap();
+                stream.write_u16(0).await.unwrap();
+            }
+            stream.flush().await.unwrap();
+        }
+    }
+
+    /// Hands out the current token and records the expired ones
+    #[derive(Default)]
+    pub(crate) struct TestAuthManager {
+        pub(crate) current: std::sync::Mutex<Option<AuthToken>>,
+        pub(crate) expired: std::sync::Mutex<Vec<AuthToken>>,
+    }
+
+    impl TestAuthManager {
+        pub(crate) fn new(token: AuthToken) -> Arc<TestAuthManager> {
+            let manager = TestAuthManager::default();
+            *manager.current.lock().unwrap() = Some(token);
+            Arc::new(manager)
+        }
+
+        pub(crate) fn set(&self, token: AuthToken) {
+            *self.current.lock().unwrap() = Some(token);
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl AuthTokenManager for TestAuthManager {
+        async fn get_token(&self) -> Result<AuthToken> {
+            Ok(self.current.lock().unwrap().clone().unwrap())
+        }
+
+        async fn on_token_expired(&self, token: &AuthToken) {
+            self.expired.lock().unwrap().push(token.clone());
+        }
+    }
+
+    async fn connect(uri: &str) -> Result<Connection> {
+        let info = ConnectionInfo::new(
+            uri,
+            Arc::new(AuthToken::basic("user", "pass")),
+            &TlsConfig::default(),
+        )?;
+        Connection::new(&info).await
+    }
+
+    fn run() -> BoltRequest {
+        BoltRequest::run("", "RETURN 1", BoltMap::default(), BoltMap::default())
+    }
+
+    #[tokio::test]
+    async fn should_not_send_requests_while_failed() {
+        let uri = stub_server(vec![
+            vec![success()],
+            vec![failure("Neo.ClientError.Statement.SyntaxError")],
+            vec![success()],
+        ])
+        .await;
+        let mut connection = connect(&uri).await.unwrap();
+        assert_eq!(connection.state, ConnectionState::Ready);
+
+        let response = connection.send_recv(run()).await.unwrap();
+        assert!(matches!(response, BoltResponse::Failure(_)));
+        assert!(connection.is_failed());
+
+        let pull = connection.send(BoltRequest::pull(10, -1)).await;
+        assert!(matches!(pull, Err(Error::Ignored)));
+
+        connection.reset().await.unwrap();
+        assert_eq!(connection.state, ConnectionState::Ready);
+    }
+
+    #[tokio::test]
+    async fn should_authenticate_with_the_auth_token() {
+        let (uri, requests) = recording_stub_server(vec![vec![success()]]).await;
+        let info = ConnectionInfo::new(
+            &uri,
+            Arc::new(AuthToken::bearer("jwt")),
+            &TlsConfig::default(),
+        )
+        .unwrap();
+
+        Connection::new(&info).await.unwrap();
+
+        let requests = requests.lock().unwrap();
+        let contains = |value: &[u8]| requests[0].windows(value.len()).any(|w| w == value);
+        assert!(contains(b"\x86scheme\x86bearer"));
+        assert!(contains(b"\x8Bcredentials\x83jwt"));
+        assert!(!contains(b"principal"));
+    }
+
+    #[tokio::test]
+    async fn should_send_utc_datetimes_once_the_server_acknowledges_the_patch() {
+        let datetime = chrono::DateTime::parse_from_rfc3339("2024-01-01T12:00:00+01:00").unwrap();
+        let run = || {
+            let mut params = BoltMap::default();
+            params.put("at".into(), datetime.into());
+            BoltRequest::run("", "RETURN $at", params, BoltMap::default())
+        };
+        let mut patched = BoltMap::default();
+        patched.put("patch_bolt".into(), vec!["utc"].into());
+        let (uri, requests) =
+            recording_stub_server(vec![vec![success_with(patched)], vec![success()]]).await;
+        let mut connection = connect(&uri).await.unwrap();
+        connection.send_recv(run()).await.unwrap();
+
+        let (legacy_uri, legacy_requests) =
+            recording_stub_server(vec![vec![success()], vec![success()]]).await;
+        let mut legacy = connect(&legacy_uri).await.unwrap();
+        legacy.send_recv(run()).await.unwrap();
+
+        let requests = requests.lock().unwrap();
+        let contains =
+            |request: &Bytes, value: &[u8]| request.windows(value.len()).any(|w| w == value);
+        assert!(contains(&requests[0], b"\x8Apatch_bolt\x91\x83utc"));
+        assert!(connection.version().uses_utc_datetime());
+        assert!(contains(&requests[1], &[0xB3, 0x49]));
+        assert!(!legacy.version().uses_utc_datetime());
+        assert!(contains(&legacy_requests.lock().unwrap()[1], &[0xB3, 0x46]));
+    }
+
+    #[tokio::test]
+    async fn should_report_an_expired_token_to_the_manager() {
+        let uri = versioned_stub_server(
+            [0, 0, 1, 5],
+            vec![
+                vec![success()],
+                vec![failure("Neo.ClientError.Security.TokenExpired")],
+            ],
+        )
+        .await
+        .0;
+        let manager = TestAuthManager::new(AuthToken::bearer("old"));
+        let info = ConnectionInfo::new(&uri, manager.clone(), &TlsConfig::default()).unwrap();
+
+        let error = Connection::new(&info).await.unwrap_err();
+
+        assert!(error.is_token_expired());
+        assert_eq!(
+            *manager.expired.lock().unwrap(),
+            vec![AuthToken::bearer("old")]
+        );
+    }
+
+    #[tokio::test]
+    async fn should_reauthenticate_with_logoff_and_logon() {
+        let (uri, requests) = versioned_stub_server(
+            [0, 0, 1, 5],
+            vec![
+                vec![success()],
+                vec![success()],
+                vec![failure("Neo.ClientError.Security.TokenExpired")],
+                vec![success()],
+                vec![],
+                vec![success(), success()],
+            ],
+        )
+        .await;
+        let info = ConnectionInfo::new(
+            &uri,
+            Arc::new(AuthToken::bearer("old")),
+            &TlsConfig::default(),
+        )
+        .unwrap();
+        let mut connection = Connection::new(&info).await.unwrap();
+        connection.send_recv(run()).await.unwrap();
+        assert!(connection.is_token_expired());
+
+        connection.reset().await.unwrap();
+        connection
+            .reauthenticate(AuthToken::bearer("new"))
+            .await
+            .unwrap();
+
+        assert!(!connection.is_token_expired());
+        assert_eq!(connection.auth_token(), &AuthToken::bearer("new"));
+        assert_eq!(connection.state, ConnectionState::Ready);
+        let requests = requests.lock().unwrap();
+        assert_eq!(requests[4], request_bytes(BoltRequest::logoff()));
+        assert!(requests[5]
+            .windows(b"\x8Bcredentials\x83new".len())
+            .any(|w| w == b"\x8Bcredentials\x83new"));
+    }
+
+    #[tokio::test]
+    async fn should_time_out_servers_not_answering_hello() {
+        let uri = stub_server(vec![vec![], vec![]]).await;
+        let mut info = ConnectionInfo::new(
+            &uri,
+            Arc::new(AuthToken::basic("user", "pass")),
+            &TlsConfig::default(),
+        )
+        .unwrap();
+        info.connect_timeout = Duration::from_millis(100);
+
+        match Connection::new(&info).await {
+            Err(Error::ConnectTimeout(address)) => assert_eq!(address, uri),
+            result => panic!("expected a connect timeout, got {:?}", result.map(|_| ())),
+        }
+    }
+
+    #[tokio::test]
+    async fn should_keep_the_token_when_logon_fails() {
+        let uri = versioned_stub_server(
+            [0, 0, 1, 5],
+            vec![
+                vec![success()],
+                vec![success()],
+                vec![],
+                vec![success(), failure("Neo.ClientError.Security.Unauthorized")],
+            ],
+        )
+        .await
+        .0;
+        let info = ConnectionInfo::new(
+            &uri,
+            Arc::new(AuthToken::bearer("old")),
+            &TlsConfig::default(),
+        )
+        .unwrap();
+        let mut connection = Connection::new(&info).await.unwrap();
+
+        let result = connection.reauthenticate(AuthToken::bearer("new")).await;
+
+        assert!(matches!(result, Err(Error::AuthenticationError(_))));
+        assert_eq!(connection.auth_token(), &AuthToken::bearer("old"));
+    }
+
+    #[tokio::test]
+    async fn should_report_ignored_responses() {
+        let uri = stub_server(vec![
+            vec![success()],
+            vec![],
+            vec![failure("Neo.ClientError.Statement.SyntaxError"), ignored()],
+            vec![success()],
+        ])
+        .await;
+        let mut connection = connect(&uri).await.unwrap();
+
+        connection.send(run()).await.unwrap();
+        connection.send(BoltRequest::pull(10, -1)).await.unwrap();
+        assert!(matches!(
+            connection.recv().await.unwrap(),
+            BoltResponse::Failure(_)
+        ));
+        assert!(matches!(connection.recv().await, Err(Error::Ignored)));
+        assert!(connection.pending.is_empty());
+
+        connection.reset().await.unwrap();
+        assert_eq!(connection.state, ConnectionState::Ready);
+    }
+
+    #[tokio::test]
+    async fn should_track_transaction_states() {
+        let uri = stub_server(vec![
+            vec![success()],
+            vec![success()],
+            vec![success()],
+            vec![Bytes::from_static(&[
+                0xB1, 0x70, 0xA1, 0x88, b'h', b'a', b's', b'_', b'm', b'o', b'r', b'e', 0xC3,
+            ])],
+            vec![success()],
+            vec![success()],
+        ])
+        .await;
+        let mut connection = connect(&uri).await.unwrap();
+
+        connection
+            .send_recv(BoltRequest::begin(BoltMap::default()))
+            .await
+            .unwrap();
+        assert_eq!(connection.state, ConnectionState::TxReady);
+        connection.send_recv(run()).await.unwrap();
+        assert_eq!(connection.state, ConnectionState::TxStreaming);
+        connection
+            .send_recv(BoltRequest::pull(1, -1))
+            .await
+            .unwrap();
+        assert_eq!(connection.state, ConnectionState::TxStreaming);
+        connection
+            .send_recv(BoltRequest::pull(1, -1))
+            .await
+            .unwrap();
+        assert_eq!(connection.state, ConnectionState::TxReady);
+        connection.send_recv(BoltRequest::commit()).await.unwrap();
+        assert_eq!(connection.state, ConnectionState::Ready);
+    }
+
+    #[tokio::test]
+    async fn should_become_defunct_when_the_server_goes_away() {
+        let uri = stub_server(vec![vec![success()]]).await;
+        let mut connection = connect(&uri).await.unwrap();
+
+        assert!(connection.send_recv(run()).await.is_err());
+        assert!(connection.is_defunct());
+        assert!(matches!(
+            connection.send(run()).await,
+            Err(Error::ConnectionError)
+        ));
+    }
+
+    #[tokio::test]
+    async fn should_report_the_failure_of_deferred_requests() {
+        let uri = stub_server(vec![
+            vec![success()],
+            vec![],
+            vec![
+                failure("Neo.ClientError.Database.DatabaseNotFound"),
+                ignored(),
+            ],
+            vec![success()],
+        ])
+        .await;
+        let mut connection = connect(&uri).await.unwrap();
+
+        connection
+            .send_deferred(BoltRequest::begin(BoltMap::default()))
+            .await
+            .unwrap();
+        let result = connection.send_recv(run()).await;
+
+        match result {
+            Err(Error::Neo4jError(e)) => assert_eq!(e.title, "DatabaseNotFound"),
+            result => panic!("expected the failure of BEGIN, got {:?}", result),
+        }
+        assert!(connection.is_failed());
+        assert!(connection.pending.is_empty());
+        connection.reset().await.unwrap();
+        assert_eq!(connection.state, ConnectionState::Ready);
+    }
+
+    #[tokio::test]
+    async fn should_buffer_the_results_of_other_streams() {
+        let uri = stub_server(vec![
+            vec![success()],
+            vec![record(vec![BoltType::from(1)].into()), success()],
+            vec![record(vec![BoltType::from(2)].into()), success()],
+            vec![success()],
+        ])
+        .await;
+        let mut connection = connect(&uri).await.unwrap();
+
+        connection
+            .send_for(1, BoltRequest::pull(10, 1))
+            .await
+            .unwrap();
+        connection
+            .send_for(2, BoltRequest::pull(10, 2))
+            .await
+            .unwrap();
+        assert!(matches!(
+            connection.send_recv(run()).await.unwrap(),
+            BoltResponse::Success(_)
+        ));
+        assert!(connection.pending.is_empty());
+
+        let record = |response| match response {
+            Ok(BoltResponse::Record(record)) => record.data,
+            response => panic!("expected a record, got {:?}", response),
+        };
+        assert_eq!(
+            record(connection.recv_for(2).await),
+            vec![BoltType::from(2)].into()
+        );
+        assert!(matches!(
+            connection.recv_for(2).await,
+            Ok(BoltResponse::Success(_))
+        ));
+        assert_eq!(
+            record(connection.recv_for(1).await),
+            vec![BoltType::from(1)].into()
+        );
+        assert!(matches!(
+            connection.recv_for(1).await,
+            Ok(BoltResponse::Success(_))
+        ));
+    }
+
+    #[cfg(feature = "rustls")]
+    mod tls {
+        use super::*;
+        use rcgen::{BasicConstraints, Certificate, CertificateParams, IsCa};
+        use rustls::pki_types::{CertificateDer, PrivateKeyDer};
+        use rustls::server::WebPkiClientVerifier;
+        use rustls::{RootCertStore, ServerConfig};
+        use std::path::PathBuf;
+        use std::sync::Arc;
+        use tokio_rustls::TlsAcceptor;
+
+        /// A CA issuing certificates into a temporary directory, removed again on drop
+        struct Certificates {
+            ca: Certificate,
+            dir: PathBuf,
+        }
+
+        impl Drop for Certificates {
+            fn drop(&mut self) {
+                let _ = std::fs::remove_dir_all(&self.dir);
+            }
+        }
+
+        impl Certificates {
+            fn new(name: &str) -> Certificates {
+                let mut params = CertificateParams::new(Vec::<String>::new());
+                params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+                let ca = Certificate::from_params(params).unwrap();
+                let dir =
+                    std::env::temp_dir().join(format!("neo4rs-{}-{}", name, std::process::id()));
+                std::fs::create_dir_all(&dir).unwrap();
+                std::fs::write(dir.join("ca.pem"), ca.serialize_pem().unwrap()).unwrap();
+                Certificates { ca, dir }
+            }
+
+            /// Writes a certificate signed by the CA and its key, returns their paths
+            fn issue(&self, name: &str) -> (PathBuf, PathBuf, CertificateDer<'static>, Vec<u8>) {
+                let certificate =
+                    Certificate::from_params(CertificateParams::new(vec!["localhost".to_owned()]))
+                        .unwrap();
+                let der = certificate.serialize_der_with_signer(&self.ca).unwrap();
+                let cert_path = self.dir.join(format!("{}.pem", name));
+                let key_path = self.dir.join(format!("{}.key", name));
+                std::fs::write(
+                    &cert_path,
+                    certificate.serialize_pem_with_signer(&self.ca).unwrap(),
+                )
+                .unwrap();
+                std::fs::write(&key_path, certificate.serialize_private_key_pem()).unwrap();
+                (
+                    cert_path,
+                    key_path,
+                    der.into(),
+                    certificate.serialize_private_key_der(),
+                )
+            }
+
+            fn ca_path(&self) -> PathBuf {
+                self.dir.join("ca.pem")
+            }
+
+            fn server_config(&self, require_client_auth: bool) -> ServerConfig {
+                let (_, _, certificate, key) = self.issue("server");
+                let provider = Arc::new(rustls::crypto::ring::default_provider());
+                let builder = ServerConfig::builder_with_provider(provider.clone())
+                    .with_safe_default_protocol_versions()
+                    .unwrap();
+                let builder = if require_client_auth {
+                    let mut roots = RootCertStore::empty();
+                    roots.add(self.ca.serialize_der().unwrap().into()).unwrap();
+                    let verifier =
+                        WebPkiClientVerifier::builder_with_provider(Arc::new(roots), provider)
+                            .build()
+                            .unwrap();
+                    builder.with_client_cert_verifier(verifier)
+                } else {
+                    builder.with_no_client_auth()
+                };
+                builder
+                    .with_single_cert(vec![certificate], PrivateKeyDer::Pkcs8(key.into()))
+                    .unwrap()
+            }
+        }
+
+        /// Like [`stub_server`] but speaking TLS, answers HELLO with SUCCESS
+        async fn tls_stub_server(config: ServerConfig) -> u16 {
+            let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let port = listener.local_addr().unwrap().port();
+            let acceptor = TlsAcceptor::from(Arc::new(config));
+            tokio::spawn(async move {
+                let (stream, _) = listener.accept().await.unwrap();
+                if let Ok(stream) = acceptor.accept(stream).await {
+                    serve(stream, vec![vec![success()]]).await;
+                }
+            });
+            port
+        }
+
+        async fn connect_with(uri: &str, tls: &TlsConfig) -> Result<Connection> {
+            let info = ConnectionInfo::new(uri, Arc::new(AuthToken::basic("user", "pass")), tls)?;
+            Connection::new(&info).await
+        }
+
+        #[tokio::test]
+        async fn should_connect_with_a_trusted_ca() {
+            let certificates = Certificates::new("trusted");
+            let port = tls_stub_server(certificates.server_config(false)).await;
+            let tls = TlsConfig {
+                ca_certificate: Some(certificates.ca_path()),
+                client_certificate: None,
+            };
+
+            let connection = connect_with(&format!("bolt+s://localhost:{}", port), &tls)
+                .await
+                .unwrap();
+            assert_eq!(connection.state, ConnectionState::Ready);
+        }
+
+        #[tokio::test]
+        async fn should_reject_unknown_certificates() {
+            let certificates = Certificates::new("unknown");
+            let port = tls_stub_server(certificates.server_config(false)).await;
+
+            let result = connect_with(
+                &format!("neo4j+s://localhost:{}", port),
+                &TlsConfig::default(),
+            )
+            .await;
+            assert!(matches!(result, Err(Error::TlsError(_))));
+        }
+
+        #[tokio::test]
+        async fn should_accept_self_signed_certificates() {
+            let certificates = Certificates::new("self-signed");
+            let port = tls_stub_server(certificates.server_config(false)).await;
+
+            let connection = connect_with(
+                &format!("bolt+ssc://localhost:{}", port),
+                &TlsConfig::default(),
+            )
+            .await
+            .unwrap();
+            assert_eq!(connection.state, ConnectionState::Ready);
+        }
+
+        #[tokio::test]
+        async fn should_present_client_certificates() {
+            let certificates = Certificates::new("client-auth");
+            let port = tls_stub_server(certificates.server_config(true)).await;
+            let (cert, key, _, _) = certificates.issue("client");
+            let tls = TlsConfig {
+                ca_certificate: Some(certificates.ca_path()),
+                client_certificate: Some((cert, key)),
+            };
+
+            let connection = connect_with(&format!("bolt+s://localhost:{}", port), &tls)
+                .await
+                .unwrap();
+            assert_eq!(connection.state, ConnectionState::Ready);
+        }
+
+        #[tokio::test]
+        async fn should_report_missing_certificate_files() {
+            let tls = TlsConfig {
+                ca_certificate: Some("/does/not/exist.pem".into()),
+                client_certificate: None,
+            };
+            assert!(ConnectionInfo::new(
+                "bolt+s://localhost",
+                Arc::new(AuthToken::basic("user", "pass")),
+                &tls
+            )
+            .is_err());
+        }
+    }
+}
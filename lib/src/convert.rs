@@ -208,6 +208,16 @@ impl TryFrom<BoltType> for BoltList {
     }
 }
 
+impl TryFrom<BoltType> for BoltMap {
+    type Error = Error;
+    fn try_from(input: BoltType) -> Result<BoltMap> {
+        match input {
+            BoltType::Map(m) => Ok(m),
+            _ => Err(Error::ConversionError),
+        }
+    }
+}
+
 impl TryFrom<BoltType> for BoltString {
     type Error = Error;
     fn try_from(input: BoltType) -> Result<BoltString> {
@@ -286,6 +296,26 @@ impl<A: Into<BoltType> + Clone> From<&[A]> for BoltType {
     }
 }
 
+impl<K: Into<BoltString>, V: Into<BoltType>> From<std::collections::HashMap<K, V>> for BoltType {
+    fn from(value: std::collections::HashMap<K, V>) -> Self {
+        BoltType::Map(
+            value
+                .into_iter()
+                .map(|(k, v)| (k.into(), v.into()))
+                .collect(),
+        )
+    }
+}
+
+impl<A: Into<BoltType>> From<Option<A>> for BoltType {
+    fn from(value: Option<A>) -> Self {
+        match value {
+            Some(value) => value.into(),
+            None => BoltType::Null(BoltNull),
+        }
+    }
+}
+
 impl From<Vec<u8>> for BoltType {
     fn from(value: Vec<u8>) -> Self {
         BoltType::Bytes(BoltBytes::new(value.into()))
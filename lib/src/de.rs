@@ -0,0 +1,526 @@
+use crate::errors::*;
+use crate::types::*;
+use chrono::{DateTime, FixedOffset, NaiveDate, NaiveDateTime, NaiveTime};
+use serde::de::value::{BorrowedStrDeserializer, MapDeserializer, SeqDeserializer};
+use serde::de::{
+    self, DeserializeSeed, Deserializer, IntoDeserializer, MapAccess, SeqAccess, Unexpected,
+    Visitor,
+};
+use serde::forward_to_deserialize_any;
+use std::collections::hash_map;
+use std::convert::TryInto;
+
+type DeResult<T> = std::result::Result<T, DeError>;
+
+impl BoltType {
+    /// Deserialize the value into any type implementing [`serde::Deserialize`].
+    ///
+    /// Maps, nodes and relationships deserialize like maps of their properties, lists like
+    /// sequences and null like `None`. Temporal values are handed out as ISO 8601 strings, which
+    /// is what the chrono types expect, except for datetimes with a zone id which deserialize
+    /// like a `(NaiveDateTime, String)` tuple. Points deserialize like a map with the keys
+    /// `sr_id`, `x`, `y` and `z` and durations like a [`std::time::Duration`].
+    pub fn to<'this, T: de::Deserialize<'this>>(&'this self) -> Result<T> {
+        T::deserialize(BoltTypeDeserializer::new(self)).map_err(Error::from)
+    }
+}
+
+impl BoltMap {
+    /// Deserialize the map into any type implementing [`serde::Deserialize`], see [`BoltType::to`]
+    pub fn to<'this, T: de::Deserialize<'this>>(&'this self) -> Result<T> {
+        T::deserialize(BoltMapDeserializer(self)).map_err(Error::from)
+    }
+}
+
+pub struct BoltTypeDeserializer<'de> {
+    value: &'de BoltType,
+}
+
+impl<'de> BoltTypeDeserializer<'de> {
+    pub fn new(value: &'de BoltType) -> Self {
+        BoltTypeDeserializer { value }
+    }
+}
+
+impl<'de> Deserializer<'de> for BoltTypeDeserializer<'de> {
+    type Error = DeError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> DeResult<V::Value> {
+        match self.value {
+            BoltType::String(s) => visitor.visit_borrowed_str(&s.value),
+            BoltType::Boolean(b) => visitor.visit_bool(b.value),
+            BoltType::Map(m) => visitor.visit_map(BoltMapAccess::new(m)),
+            BoltType::Null(_) => visitor.visit_unit(),
+            BoltType::Integer(i) => visitor.visit_i64(i.value),
+            BoltType::Float(f) => visitor.visit_f64(f.value),
+            BoltType::List(l) => visitor.visit_seq(BoltSeqAccess::new(&l.value)),
+            BoltType::Node(n) => visitor.visit_map(BoltMapAccess::new(&n.properties)),
+            BoltType::Relation(r) => visitor.visit_map(BoltMapAccess::new(&r.properties)),
+            BoltType::UnboundedRelation(r) => visitor.visit_map(BoltMapAccess::new(&r.properties)),
+            BoltType::Point2D(p) => visitor.visit_map(MapDeserializer::new(
+                vec![
+                    ("sr_id", Coordinate::SrId(p.sr_id.value)),
+                    ("x", Coordinate::Value(p.x.value)),
+                    ("y", Coordinate::Value(p.y.value)),
+                ]
+                .into_iter(),
+            )),
+            BoltType::Point3D(p) => visitor.visit_map(MapDeserializer::new(
+                vec![
+                    ("sr_id", Coordinate::SrId(p.sr_id.value)),
+                    ("x", Coordinate::Value(p.x.value)),
+                    ("y", Coordinate::Value(p.y.value)),
+                    ("z", Coordinate::Value(p.z.value)),
+                ]
+                .into_iter(),
+            )),
+            BoltType::Bytes(b) => visitor.visit_borrowed_bytes(&b.value),
+            BoltType::Path(_) => Err(de::Error::invalid_type(Unexpected::Other("path"), &visitor)),
+            BoltType::Duration(d) => {
+                let duration: std::time::Duration = d.clone().into();
+                visitor.visit_map(MapDeserializer::new(
+                    vec![
+                        ("secs", duration.as_secs()),
+                        ("nanos", u64::from(duration.subsec_nanos())),
+                    ]
+                    .into_iter(),
+                ))
+            }
+            BoltType::Date(d) => {
+                let date: NaiveDate = d.clone().try_into().map_err(de::Error::custom)?;
+                visitor.visit_string(date.format("%Y-%m-%d").to_string())
+            }
+            BoltType::Time(t) => {
+                let (time, offset): (NaiveTime, FixedOffset) = t.clone().into();
+                visitor.visit_string(format!("{}{}", time.format("%H:%M:%S%.f"), offset))
+            }
+            BoltType::LocalTime(t) => {
+                let time: NaiveTime = t.clone().into();
+                visitor.visit_string(time.format("%H:%M:%S%.f").to_string())
+            }
+            BoltType::DateTime(d) => {
+                let datetime: DateTime<FixedOffset> =
+                    d.clone().try_into().map_err(de::Error::custom)?;
+                visitor.visit_string(datetime.to_rfc3339())
+            }
+            BoltType::LocalDateTime(d) => {
+                let datetime: NaiveDateTime = d.clone().try_into().map_err(de::Error::custom)?;
+                visitor.visit_string(datetime.format("%Y-%m-%dT%H:%M:%S%.f").to_string())
+            }
+            BoltType::DateTimeZoneId(d) => {
+                let (datetime, zone): (NaiveDateTime, String) =
+                    d.clone().try_into().map_err(de::Error::custom)?;
+                visitor.visit_seq(SeqDeserializer::new(
+                    vec![datetime.format("%Y-%m-%dT%H:%M:%S%.f").to_string(), zone].into_iter(),
+                ))
+            }
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> DeResult<V::Value> {
+        match self.value {
+            BoltType::Null(_) => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> DeResult<V::Value> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> DeResult<V::Value> {
+        match self.value {
+            BoltType::Bytes(b) => visitor.visit_seq(SeqDeserializer::new(b.value.iter().copied())),
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(self, _len: usize, visitor: V) -> DeResult<V::Value> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> DeResult<V::Value> {
+        match self.value {
+            BoltType::String(s) => visitor.visit_enum(s.value.as_str().into_deserializer()),
+            _ => Err(de::Error::invalid_type(self.unexpected(), &visitor)),
+        }
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct tuple_struct map struct identifier ignored_any
+    }
+}
+
+impl<'de> BoltTypeDeserializer<'de> {
+    fn unexpected(&self) -> Unexpected<'de> {
+        match self.value {
+            BoltType::String(s) => Unexpected::Str(&s.value),
+            BoltType::Boolean(b) => Unexpected::Bool(b.value),
+            BoltType::Integer(i) => Unexpected::Signed(i.value),
+            BoltType::Float(f) => Unexpected::Float(f.value),
+            BoltType::Null(_) => Unexpected::Unit,
+            BoltType::List(_) => Unexpected::Seq,
+            BoltType::Map(_) => Unexpected::Map,
+            BoltType::Bytes(b) => Unexpected::Bytes(&b.value),
+            BoltType::Node(_) => Unexpected::Other("node"),
+            BoltType::Relation(_) | BoltType::UnboundedRelation(_) => {
+                Unexpected::Other("relationship")
+            }
+            BoltType::Path(_) => Unexpected::Other("path"),
+            BoltType::Point2D(_) | BoltType::Point3D(_) => Unexpected::Other("point"),
+            _ => Unexpected::Other("temporal value"),
+        }
+    }
+}
+
+/// Deserializes the properties of a row, node or relationship
+pub struct BoltMapDeserializer<'de>(pub &'de BoltMap);
+
+impl<'de> Deserializer<'de> for BoltMapDeserializer<'de> {
+    type Error = DeError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> DeResult<V::Value> {
+        visitor.visit_map(BoltMapAccess::new(self.0))
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> DeResult<V::Value> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> DeResult<V::Value> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct seq tuple tuple_struct map struct enum
+        identifier ignored_any
+    }
+}
+
+struct BoltMapAccess<'de> {
+    entries: hash_map::Iter<'de, BoltString, BoltType>,
+    value: Option<(&'de str, &'de BoltType)>,
+}
+
+impl<'de> BoltMapAccess<'de> {
+    fn new(map: &'de BoltMap) -> Self {
+        BoltMapAccess {
+            entries: map.value.iter(),
+            value: None,
+        }
+    }
+}
+
+impl<'de> MapAccess<'de> for BoltMapAccess<'de> {
+    type Error = DeError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(&mut self, seed: K) -> DeResult<Option<K::Value>> {
+        match self.entries.next() {
+            Some((key, value)) => {
+                self.value = Some((&key.value, value));
+                seed.deserialize(BorrowedStrDeserializer::new(&key.value))
+                    .map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<S: DeserializeSeed<'de>>(&mut self, seed: S) -> DeResult<S::Value> {
+        let (key, value) = self
+            .value
+            .take()
+            .ok_or_else(|| de::Error::custom("value requested before key"))?;
+        seed.deserialize(BoltTypeDeserializer::new(value))
+            .map_err(|e| e.in_field(key))
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.entries.len())
+    }
+}
+
+struct BoltSeqAccess<'de> {
+    items: std::iter::Enumerate<std::slice::Iter<'de, BoltType>>,
+}
+
+impl<'de> BoltSeqAccess<'de> {
+    fn new(items: &'de [BoltType]) -> Self {
+        BoltSeqAccess {
+            items: items.iter().enumerate(),
+        }
+    }
+}
+
+impl<'de> SeqAccess<'de> for BoltSeqAccess<'de> {
+    type Error = DeError;
+
+    fn next_element_seed<S: DeserializeSeed<'de>>(
+        &mut self,
+        seed: S,
+    ) -> DeResult<Option<S::Value>> {
+        match self.items.next() {
+            Some((index, value)) => seed
+                .deserialize(BoltTypeDeserializer::new(value))
+                .map(Some)
+                .map_err(|e| e.in_index(index)),
+            None => Ok(None),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.items.len())
+    }
+}
+
+/// The spatial reference id of a point is an integer while the coordinates are floats
+enum Coordinate {
+    SrId(i64),
+    Value(f64),
+}
+
+impl<'de> IntoDeserializer<'de, DeError> for Coordinate {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+impl<'de> Deserializer<'de> for Coordinate {
+    type Error = DeError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> DeResult<V::Value> {
+        match self {
+            Coordinate::SrId(v) => visitor.visit_i64(v),
+            Coordinate::Value(v) => visitor.visit_f64(v),
+        }
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct newtype_struct seq tuple
+        tuple_struct map struct enum identifier ignored_any
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use serde::Deserialize;
+    use std::collections::HashMap;
+
+    fn map(entries: Vec<(&str, BoltType)>) -> BoltMap {
+        entries.into_iter().map(|(k, v)| (k.into(), v)).collect()
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Person {
+        name: String,
+        age: u8,
+        nickname: Option<String>,
+        tags: Vec<String>,
+    }
+
+    #[test]
+    fn should_deserialize_a_struct() {
+        let value = map(vec![
+            ("name", "Mark".into()),
+            ("age", 42.into()),
+            ("nickname", BoltType::Null(BoltNull)),
+            ("tags", vec!["a", "b"].into()),
+        ]);
+
+        let person: Person = value.to().unwrap();
+
+        assert_eq!(
+            person,
+            Person {
+                name: "Mark".into(),
+                age: 42,
+                nickname: None,
+                tags: vec!["a".into(), "b".into()],
+            }
+        );
+    }
+
+    #[test]
+    fn should_deserialize_nested_values() {
+        #[derive(Debug, PartialEq, Deserialize)]
+        struct Team<'a> {
+            name: &'a str,
+            scores: HashMap<String, f64>,
+            members: Vec<Person>,
+        }
+
+        let member = map(vec![
+            ("name", "Jake".into()),
+            ("age", 7.into()),
+            ("tags", BoltType::List(BoltList::new())),
+        ]);
+        let value = BoltType::Map(map(vec![
+            ("name", "rust".into()),
+            ("scores", BoltType::Map(map(vec![("q1", 1.5.into())]))),
+            ("members", vec![BoltType::Map(member)].into()),
+        ]));
+
+        let team: Team = value.to().unwrap();
+
+        assert_eq!(team.name, "rust");
+        assert_eq!(team.scores["q1"], 1.5);
+        assert_eq!(team.members[0].name, "Jake");
+        assert_eq!(team.members[0].nickname, None);
+    }
+
+    #[test]
+    fn should_name_the_offending_field() {
+        let member = map(vec![
+            ("name", "Jake".into()),
+            ("age", 300.into()),
+            ("tags", BoltType::List(BoltList::new())),
+        ]);
+        let value = map(vec![("members", vec![BoltType::Map(member)].into())]);
+
+        #[derive(Debug, Deserialize)]
+        struct Team {
+            #[allow(dead_code)]
+            members: Vec<Person>,
+        }
+
+        match value.to::<Team>() {
+            Err(Error::Deserialize(e)) => {
+                assert_eq!(e.field(), Some("members[0].age"));
+                assert!(e.message().contains("300"), "{}", e);
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn should_report_missing_fields() {
+        let error = map(vec![("name", "Mark".into())])
+            .to::<Person>()
+            .unwrap_err();
+
+        assert_eq!(error.to_string(), "missing field `age`");
+    }
+
+    #[test]
+    fn should_deserialize_nodes_and_relationships_as_their_properties() {
+        #[derive(Debug, PartialEq, Deserialize)]
+        struct Knows {
+            since: i64,
+        }
+
+        let node = BoltType::Node(BoltNode::new(
+            1.into(),
+            vec!["Person".into()].into(),
+            map(vec![
+                ("name", "Mark".into()),
+                ("age", 42.into()),
+                ("tags", BoltType::List(BoltList::new())),
+            ]),
+        ));
+        let relation = BoltType::Relation(BoltRelation::new(
+            2.into(),
+            1.into(),
+            3.into(),
+            "KNOWS".into(),
+            map(vec![("since", 2020.into())]),
+        ));
+
+        assert_eq!(node.to::<Person>().unwrap().name, "Mark");
+        assert_eq!(relation.to::<Knows>().unwrap(), Knows { since: 2020 });
+    }
+
+    #[test]
+    fn should_deserialize_temporal_values() {
+        let date = NaiveDate::from_ymd_opt(2021, 6, 27).unwrap();
+        let time = NaiveTime::from_hms_nano_opt(10, 15, 30, 200).unwrap();
+        let local = NaiveDateTime::new(date, time);
+        let offset = FixedOffset::east_opt(3600).unwrap();
+        let datetime = offset.from_utc_datetime(&local);
+
+        assert_eq!(BoltType::from(date).to::<NaiveDate>().unwrap(), date);
+        assert_eq!(BoltType::from(time).to::<NaiveTime>().unwrap(), time);
+        assert_eq!(BoltType::from(local).to::<NaiveDateTime>().unwrap(), local);
+        assert_eq!(
+            BoltType::from(datetime)
+                .to::<DateTime<FixedOffset>>()
+                .unwrap(),
+            datetime
+        );
+        assert_eq!(
+            BoltType::from((local, "Europe/Paris"))
+                .to::<(NaiveDateTime, String)>()
+                .unwrap(),
+            (local, "Europe/Paris".to_owned())
+        );
+        assert_eq!(
+            BoltType::from((time, offset)).to::<String>().unwrap(),
+            "10:15:30.000000200+01:00"
+        );
+        assert_eq!(
+            BoltType::from(std::time::Duration::new(5, 6))
+                .to::<std::time::Duration>()
+                .unwrap(),
+            std::time::Duration::new(5, 6)
+        );
+    }
+
+    #[test]
+    fn should_deserialize_points_and_bytes() {
+        #[derive(Debug, PartialEq, Deserialize)]
+        struct Point {
+            sr_id: i64,
+            x: f64,
+            y: f64,
+        }
+
+        let point = BoltType::Point2D(BoltPoint2D {
+            sr_id: 7203.into(),
+            x: BoltFloat::new(1.5),
+            y: BoltFloat::new(2.0),
+        });
+        let bytes = BoltType::Bytes(BoltBytes::new(bytes::Bytes::from_static(&[1, 2])));
+
+        assert_eq!(
+            point.to::<Point>().unwrap(),
+            Point {
+                sr_id: 7203,
+                x: 1.5,
+                y: 2.0
+            }
+        );
+        assert_eq!(bytes.to::<Vec<u8>>().unwrap(), vec![1, 2]);
+    }
+
+    #[test]
+    fn should_deserialize_enums_from_strings() {
+        #[derive(Debug, PartialEq, Deserialize)]
+        #[allow(dead_code)]
+        enum Role {
+            Admin,
+            User,
+        }
+
+        assert_eq!(BoltType::from("User").to::<Role>().unwrap(), Role::User);
+        assert!(BoltType::from("Root").to::<Role>().is_err());
+        assert!(BoltType::from(1).to::<Role>().is_err());
+    }
+}
@@ -1,6 +1,11 @@
 pub type Result<T> = std::result::Result<T, Error>;
 
-#[derive(Debug, thiserror::Error)]
+pub(crate) const NOT_A_LEADER: &str = "Neo.ClientError.Cluster.NotALeader";
+pub(crate) const FORBIDDEN_ON_READ_ONLY: &str =
+    "Neo.ClientError.General.ForbiddenOnReadOnlyDatabase";
+pub(crate) const TOKEN_EXPIRED: &str = "Neo.ClientError.Security.TokenExpired";
+
+#[derive(Debug, Clone, thiserror::Error)]
 pub enum Error {
     #[error("an IO error occurred")]
     IOError { detail: String },
@@ -8,6 +13,18 @@ pub enum Error {
     #[error("connection error")]
     ConnectionError,
 
+    #[error("{0}")]
+    ServiceUnavailable(String),
+
+    #[error("timed out connecting to {0}")]
+    ConnectTimeout(String),
+
+    #[error("timed out waiting for a connection from the pool")]
+    ConnectionAcquisitionTimeout,
+
+    #[error("the connection pool is closed")]
+    PoolClosed,
+
     #[error("attempted to serialize excessively long string")]
     StringTooLong,
 
@@ -23,6 +40,15 @@ pub enum Error {
     #[error("invalid config")]
     InvalidConfig,
 
+    #[error("{0}")]
+    InvalidUri(String),
+
+    #[error("unsupported uri scheme {0}")]
+    UnsupportedScheme(String),
+
+    #[error("TLS error: {0}")]
+    TlsError(String),
+
     #[error("{0}")]
     UnsupportedVersion(String),
 
@@ -44,8 +70,209 @@ pub enum Error {
     #[error("{0}")]
     InvalidTypeMarker(String),
 
+    #[deprecated(note = "invalid strings are reported as `Error::Deserialize`")]
     #[error("{0}")]
     DeserializationError(String),
+
+    #[error("{0}")]
+    SerializationError(String),
+
+    #[error("{0}")]
+    Neo4jError(Neo4jError),
+
+    #[error("{0}")]
+    InvalidTransactionState(String),
+
+    #[error("query {index} of the batch failed: {error}")]
+    BatchFailed {
+        /// The position of the failed query in the batch
+        index: usize,
+        #[source]
+        error: Box<Error>,
+    },
+
+    #[error("query {index} of the batch doesn't run in the database or as the user of the first")]
+    MixedBatch {
+        /// The position of the first query differing from the first one of the batch
+        index: usize,
+    },
+
+    #[error("the request was ignored because an earlier request on the connection failed")]
+    Ignored,
+
+    #[error("{0}")]
+    Deserialize(DeError),
+}
+
+/// An error reported by the server in a `FAILURE` message.
+///
+/// Neo4j status codes have the form `Neo.<classification>.<category>.<title>`, for example
+/// `Neo.ClientError.Schema.ConstraintValidationFailed`, see
+/// <https://neo4j.com/docs/status-codes/current/>
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("{code}: {message}")]
+pub struct Neo4jError {
+    pub code: String,
+    pub message: String,
+    pub classification: String,
+    pub category: String,
+    pub title: String,
+}
+
+impl Neo4jError {
+    pub fn new(code: String, message: String) -> Self {
+        let mut parts = code.split('.').skip(1);
+        let classification = parts.next().unwrap_or_default().to_owned();
+        let category = parts.next().unwrap_or_default().to_owned();
+        let title = parts.next().unwrap_or_default().to_owned();
+        Neo4jError {
+            code,
+            message,
+            classification,
+            category,
+            title,
+        }
+    }
+
+    /// Errors which may succeed when the operation is retried, e.g. deadlocks or leader switches
+    pub fn is_transient(&self) -> bool {
+        self.classification == "TransientError"
+    }
+
+    /// Errors caused by the client, e.g. syntax errors or constraint violations
+    pub fn is_client_error(&self) -> bool {
+        self.classification == "ClientError"
+    }
+
+    /// Errors caused by the database itself
+    pub fn is_database_error(&self) -> bool {
+        self.classification == "DatabaseError"
+    }
+
+    pub fn is_constraint_violation(&self) -> bool {
+        self.code == "Neo.ClientError.Schema.ConstraintValidationFailed"
+    }
+
+    /// Whether the server the query was sent to can't accept writes, e.g. after a leader switch
+    pub fn is_not_writable(&self) -> bool {
+        self.code == NOT_A_LEADER || self.code == FORBIDDEN_ON_READ_ONLY
+    }
+
+    /// The auth token of the connection expired, see [`crate::AuthTokenManager`]
+    pub fn is_token_expired(&self) -> bool {
+        self.code == TOKEN_EXPIRED
+    }
+
+    /// Errors worth running the transaction again for: transient errors other than explicit
+    /// terminations by a user, and writes which reached a server that is no longer the leader
+    pub fn is_retryable(&self) -> bool {
+        match self.code.as_str() {
+            "Neo.TransientError.Transaction.Terminated"
+            | "Neo.TransientError.Transaction.LockClientStopped" => false,
+            _ => self.is_transient() || self.is_not_writable(),
+        }
+    }
+}
+
+/// An error decoding a value returned by the server, or converting it into a user type with
+/// serde, see [`crate::Row::to`].
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub struct DeError {
+    message: String,
+    path: String,
+}
+
+impl DeError {
+    /// What went wrong, without the location
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// Location of the offending value, e.g. `person.friends[2].name`, or `None` if the error
+    /// happened at the top level
+    pub fn field(&self) -> Option<&str> {
+        if self.path.is_empty() {
+            None
+        } else {
+            Some(&self.path)
+        }
+    }
+
+    pub(crate) fn in_field(mut self, field: &str) -> Self {
+        self.path = if self.path.is_empty() || self.path.starts_with('[') {
+            format!("{}{}", field, self.path)
+        } else {
+            format!("{}.{}", field, self.path)
+        };
+        self
+    }
+
+    pub(crate) fn in_index(mut self, index: usize) -> Self {
+        self.path = if self.path.is_empty() || self.path.starts_with('[') {
+            format!("[{}]{}", index, self.path)
+        } else {
+            format!("[{}].{}", index, self.path)
+        };
+        self
+    }
+}
+
+impl std::fmt::Display for DeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.field() {
+            Some(field) => write!(f, "{}: {}", field, self.message),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+impl serde::de::Error for DeError {
+    fn custom<T: std::fmt::Display>(msg: T) -> Self {
+        DeError {
+            message: msg.to_string(),
+            path: String::new(),
+        }
+    }
+}
+
+impl Error {
+    /// The server error, if this error was reported by the server
+    pub fn as_neo4j_error(&self) -> Option<&Neo4jError> {
+        match self {
+            Error::Neo4jError(e) => Some(e),
+            _ => None,
+        }
+    }
+
+    /// Whether a transaction which failed with this error may succeed when run again, see
+    /// [`crate::Graph::execute_write`]
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Error::IOError { .. }
+            | Error::ConnectionError
+            | Error::ConnectTimeout(_)
+            | Error::ServiceUnavailable(_) => true,
+            Error::Neo4jError(e) => e.is_retryable(),
+            Error::BatchFailed { error, .. } => error.is_retryable(),
+            _ => false,
+        }
+    }
+
+    /// Whether the server rejected the auth token of the connection because it expired, see
+    /// [`crate::AuthTokenManager`]
+    pub fn is_token_expired(&self) -> bool {
+        match self {
+            Error::Neo4jError(e) => e.is_token_expired(),
+            Error::BatchFailed { error, .. } => error.is_token_expired(),
+            _ => false,
+        }
+    }
+}
+
+impl std::convert::From<DeError> for Error {
+    fn from(e: DeError) -> Self {
+        Error::Deserialize(e)
+    }
 }
 
 impl std::convert::From<std::io::Error> for Error {
@@ -60,6 +287,8 @@ impl std::convert::From<deadpool::managed::PoolError<Error>> for Error {
     fn from(e: deadpool::managed::PoolError<Error>) -> Self {
         match e {
             deadpool::managed::PoolError::Backend(e) => e,
+            deadpool::managed::PoolError::Timeout(_) => Error::ConnectionAcquisitionTimeout,
+            deadpool::managed::PoolError::Closed => Error::PoolClosed,
             _ => Error::ConnectionError,
         }
     }
@@ -80,3 +309,97 @@ pub fn unexpected<T: std::fmt::Debug>(response: T, request: &str) -> Error {
         request, response
     ))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_split_neo4j_error_code() {
+        let error = Neo4jError::new(
+            "Neo.ClientError.Schema.ConstraintValidationFailed".into(),
+            "already exists".into(),
+        );
+
+        assert_eq!(error.classification, "ClientError");
+        assert_eq!(error.category, "Schema");
+        assert_eq!(error.title, "ConstraintValidationFailed");
+        assert!(error.is_client_error());
+        assert!(error.is_constraint_violation());
+        assert!(!error.is_transient());
+        assert_eq!(
+            error.to_string(),
+            "Neo.ClientError.Schema.ConstraintValidationFailed: already exists"
+        );
+    }
+
+    #[test]
+    fn should_classify_transient_errors() {
+        let error = Neo4jError::new(
+            "Neo.TransientError.Transaction.DeadlockDetected".into(),
+            "deadlock".into(),
+        );
+
+        assert!(error.is_transient());
+        assert!(!error.is_client_error());
+        assert!(!error.is_database_error());
+    }
+
+    #[test]
+    fn should_classify_retryable_errors() {
+        let retryable = |code: &str| Error::Neo4jError(Neo4jError::new(code.into(), "".into()));
+
+        assert!(retryable("Neo.TransientError.Transaction.DeadlockDetected").is_retryable());
+        assert!(retryable("Neo.ClientError.Cluster.NotALeader").is_retryable());
+        assert!(!retryable("Neo.TransientError.Transaction.Terminated").is_retryable());
+        assert!(!retryable("Neo.ClientError.Statement.SyntaxError").is_retryable());
+        assert!(Error::ConnectionError.is_retryable());
+        assert!(Error::from(std::io::Error::from(std::io::ErrorKind::BrokenPipe)).is_retryable());
+        assert!(!Error::InvalidConfig.is_retryable());
+        assert!(Error::ConnectTimeout("localhost:7687".into()).is_retryable());
+        assert!(!Error::ConnectionAcquisitionTimeout.is_retryable());
+    }
+
+    #[test]
+    fn should_map_pool_errors() {
+        use deadpool::managed::{PoolError, TimeoutType};
+
+        assert!(matches!(
+            Error::from(PoolError::<Error>::Timeout(TimeoutType::Wait)),
+            Error::ConnectionAcquisitionTimeout
+        ));
+        assert!(matches!(
+            Error::from(PoolError::<Error>::Closed),
+            Error::PoolClosed
+        ));
+        assert!(matches!(
+            Error::from(PoolError::Backend(Error::InvalidConfig)),
+            Error::InvalidConfig
+        ));
+    }
+
+    #[test]
+    fn should_describe_the_path_of_a_deserialization_error() {
+        use serde::de::Error as _;
+
+        let error = DeError::custom("invalid type")
+            .in_field("name")
+            .in_index(2)
+            .in_field("friends")
+            .in_field("person");
+
+        assert_eq!(error.field(), Some("person.friends[2].name"));
+        assert_eq!(error.message(), "invalid type");
+        assert_eq!(error.to_string(), "person.friends[2].name: invalid type");
+        assert_eq!(DeError::custom("oops").field(), None);
+    }
+
+    #[test]
+    fn should_tolerate_malformed_codes() {
+        let error = Neo4jError::new("Unknown".into(), "".into());
+
+        assert_eq!(error.classification, "");
+        assert_eq!(error.category, "");
+        assert_eq!(error.title, "");
+    }
+}
@@ -1,16 +1,23 @@
 use crate::config::{config, Config};
 use crate::errors::*;
-use crate::pool::{create_pool, ConnectionPool};
+use crate::metrics::PoolMetrics;
+use crate::pool::{create_pool, server_address, ConnectionProvider};
 use crate::query::Query;
+use crate::retry::Backoff;
+use crate::routing::AccessMode;
+use crate::session::{Session, SessionConfig};
 use crate::stream::RowStream;
-use crate::txn::Txn;
+use crate::summary::ResultSummary;
+use crate::txn::{Txn, TxnConfig};
+use log::warn;
+use std::future::Future;
 use std::sync::Arc;
 use tokio::sync::Mutex;
 
 /// A neo4j database abstraction
 pub struct Graph {
-    config: Config,
-    pool: ConnectionPool,
+    pub(crate) config: Config,
+    pool: Arc<ConnectionProvider>,
 }
 
 /// Returns a [`Query`] which provides methods like [`Query::param`] to add parameters to the query
@@ -20,9 +27,10 @@ pub fn query(q: &str) -> Query {
 
 impl Graph {
     /// Connects to the database with configurations provided, you can build a config using
-    /// [`config`]
+    /// [`config`]. The first connections are opened right away, see
+    /// [`crate::ConfigBuilder::min_idle`].
     pub async fn connect(config: Config) -> Result<Self> {
-        let pool = create_pool(&config).await?;
+        let pool = Arc::new(create_pool(&config).await?);
         Ok(Graph { config, pool })
     }
 
@@ -32,11 +40,136 @@ impl Graph {
         Self::connect(config).await
     }
 
+    /// Opens a [`Session`] which shares the connection pool of the graph and runs queries and
+    /// transactions with the defaults of `config`
+    pub fn session(&self, config: SessionConfig) -> Session {
+        let graph = Graph {
+            config: self.config.clone(),
+            pool: self.pool.clone(),
+        };
+        Session::new(graph, config)
+    }
+
+    /// The state of the connection pools, one per server ordered by address. Enable the
+    /// `metrics` feature to also record it with the [`metrics`](https://docs.rs/metrics) crate.
+    pub fn metrics(&self) -> Vec<PoolMetrics> {
+        self.pool.metrics()
+    }
+
     /// Starts a new transaction, all queries that needs to be run/executed within the transaction
     /// should be executed using either [`Txn::run`] or [`Txn::execute`]
+    ///
+    /// BEGIN is sent without waiting for the server, so errors starting the transaction, like an
+    /// unknown database, are returned by its first query or by the commit.
     pub async fn start_txn(&self) -> Result<Txn> {
-        let connection = self.pool.get().await?;
-        Txn::new(self.config.clone(), connection).await
+        self.start_txn_as(AccessMode::Write).await
+    }
+
+    /// Starts a new transaction like [`Graph::start_txn`], with `neo4j` uris read transactions
+    /// are routed to the readers of the cluster and write transactions to the leader
+    pub async fn start_txn_as(&self, mode: AccessMode) -> Result<Txn> {
+        self.start_txn_with(TxnConfig::new().mode(mode)).await
+    }
+
+    /// Starts a new transaction like [`Graph::start_txn`] which sees the changes of the
+    /// transactions the bookmarks were returned by, see [`Txn::commit_with_bookmark`]
+    pub async fn start_txn_with_bookmarks(&self, bookmarks: Vec<String>) -> Result<Txn> {
+        self.start_txn_with(TxnConfig::new().bookmarks(bookmarks))
+            .await
+    }
+
+    /// Starts a new transaction like [`Graph::start_txn`] with a timeout, metadata, access mode,
+    /// database or bookmarks, see [`TxnConfig`]
+    pub async fn start_txn_with(&self, mut txn_config: TxnConfig) -> Result<Txn> {
+        if let Some(e) = txn_config.take_error() {
+            return Err(e);
+        }
+        if let Some(manager) = &self.config.bookmark_manager {
+            txn_config.add_bookmarks(manager.bookmarks());
+        }
+        let config = txn_config.apply(&self.config);
+        let connection = self
+            .pool
+            .get(
+                txn_config.access_mode(),
+                &config,
+                txn_config.sent_bookmarks(),
+            )
+            .await?;
+        let address = server_address(&connection);
+        let txn = Txn::new(config, connection, txn_config).await;
+        self.pool.check(address, txn).await
+    }
+
+    /// Runs a transaction function like [`Graph::execute_write`] in a read transaction, routed to
+    /// the readers of the cluster for `neo4j` uris
+    pub async fn execute_read<F, Fut, T>(&self, work: F) -> Result<T>
+    where
+        F: FnMut(Txn) -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        self.run_transaction(AccessMode::Read, work).await
+    }
+
+    /// Runs `work` in a write transaction which is committed once it returns successfully.
+    ///
+    /// When the transaction fails with a retryable error (see [`Error::is_retryable`]), like a
+    /// deadlock, a leader switch or a broken connection, it is rolled back and `work` is called
+    /// again with a new transaction after an exponential backoff, until the
+    /// [`crate::ConfigBuilder::max_retry_time`] is used up. A transaction rejected because the
+    /// auth token expired is run again once right away, with the token refreshed by the
+    /// [`crate::AuthTokenManager`]. `work` should therefore have no side effects outside of the
+    /// transaction, and must neither commit nor roll it back.
+    pub async fn execute_write<F, Fut, T>(&self, work: F) -> Result<T>
+    where
+        F: FnMut(Txn) -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        self.run_transaction(AccessMode::Write, work).await
+    }
+
+    async fn run_transaction<F, Fut, T>(&self, mode: AccessMode, mut work: F) -> Result<T>
+    where
+        F: FnMut(Txn) -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let mut backoff = Backoff::new(self.config.max_retry_time);
+        let mut refreshed = false;
+        loop {
+            match self.try_transaction(mode, &mut work).await {
+                Err(e) if e.is_token_expired() && !refreshed => {
+                    warn!("auth token expired, retrying with a new token");
+                    refreshed = true;
+                }
+                Err(e) if e.is_retryable() => match backoff.next_delay() {
+                    Some(delay) => {
+                        warn!("transaction failed, retrying in {:?}: {}", delay, e);
+                        tokio::time::sleep(delay).await;
+                    }
+                    None => return Err(e),
+                },
+                result => return result,
+            }
+        }
+    }
+
+    async fn try_transaction<F, Fut, T>(&self, mode: AccessMode, work: &mut F) -> Result<T>
+    where
+        F: FnMut(Txn) -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let txn = self.start_txn_as(mode).await?;
+        let address = txn.server_address().await;
+        let result = match work(txn.share()).await {
+            Ok(value) => txn.commit().await.map(|_| value),
+            Err(e) => {
+                if let Err(rollback) = txn.rollback().await {
+                    warn!("could not roll back the failed transaction: {}", rollback);
+                }
+                Err(e)
+            }
+        };
+        self.pool.check(address, result).await
     }
 
     /// Runs a query using a connection from the connection pool, it doesn't return any
@@ -46,13 +179,283 @@ impl Graph {
     ///
     /// use [`Graph::execute`] when you are interested in the result stream
     pub async fn run(&self, q: Query) -> Result<()> {
-        let connection = Arc::new(Mutex::new(self.pool.get().await?));
-        q.run(&self.config, connection).await
+        self.run_with_summary(q).await.map(|_| ())
+    }
+
+    /// Runs a query like [`Graph::run`], routed to the readers or the leader of the cluster for
+    /// `neo4j` uris
+    pub async fn run_as(&self, q: Query, mode: AccessMode) -> Result<()> {
+        self.run_with(q, TxnConfig::new().mode(mode)).await
+    }
+
+    /// Runs a query like [`Graph::run`] in an auto-commit transaction with a timeout, metadata,
+    /// access mode, database or bookmarks, see [`TxnConfig`]
+    pub async fn run_with(&self, q: Query, txn_config: TxnConfig) -> Result<()> {
+        self.run_on(q, txn_config).await.map(|_| ())
+    }
+
+    /// Runs a query like [`Graph::run`] and returns the [`ResultSummary`] with the statistics
+    /// of what the query changed.
+    pub async fn run_with_summary(&self, q: Query) -> Result<ResultSummary> {
+        self.run_on(q, TxnConfig::new()).await
+    }
+
+    /// Runs the queries one after the other on the same connection, each in its own auto-commit
+    /// transaction, sending all of them before waiting for the server. Returns the
+    /// [`ResultSummary`] of each query.
+    ///
+    /// When a query fails the ones before it stay committed and the server ignores the ones
+    /// after it, the error is an [`Error::BatchFailed`] with the index of the failed query. Use
+    /// [`Txn::run_batch`] to run all queries or none.
+    ///
+    /// All queries run on the same server and therefore have to impersonate the same user, a
+    /// batch mixing them fails with [`Error::MixedBatch`] before anything is sent.
+    pub async fn run_batch(&self, queries: Vec<Query>) -> Result<Vec<ResultSummary>> {
+        let mut batch = Vec::with_capacity(queries.len());
+        let mut sent: Vec<String> = Vec::new();
+        let mut target: Option<(Config, AccessMode)> = None;
+        for (index, q) in queries.into_iter().enumerate() {
+            let (q, config, mode) = self.auto_commit(q, TxnConfig::new())?;
+            match &target {
+                Some((first, first_mode))
+                    if first.db != config.db
+                        || first.imp_user != config.imp_user
+                        || *first_mode != mode =>
+                {
+                    return Err(Error::MixedBatch { index });
+                }
+                Some(_) => {}
+                None => target = Some((config, mode)),
+            }
+            for bookmark in q.sent_bookmarks() {
+                if !sent.contains(bookmark) {
+                    sent.push(bookmark.clone());
+                }
+            }
+            batch.push(q);
+        }
+        let (config, mode) = target.unwrap_or_else(|| (self.config.clone(), AccessMode::Write));
+        let connection = self.pool.get(mode, &config, &sent).await?;
+        let address = server_address(&connection);
+        let connection = Arc::new(Mutex::new(connection));
+        let (summaries, failure) = Query::run_batch(batch, &config, connection).await;
+        if let Some(manager) = &self.config.bookmark_manager {
+            let last = summaries.iter().rev().find_map(|s| s.bookmark.clone());
+            manager.update(&sent, last);
+        }
+        let result = match failure {
+            Some(e) => Err(e),
+            None => Ok(summaries),
+        };
+        self.pool.check(address, result).await
     }
 
     /// Executes a query and returns a [`RowStream`]
     pub async fn execute(&self, q: Query) -> Result<RowStream> {
-        let connection = Arc::new(Mutex::new(self.pool.get().await?));
-        q.execute(&self.config, connection).await
+        self.execute_with(q, TxnConfig::new()).await
+    }
+
+    /// Executes a query like [`Graph::execute`], routed to the readers or the leader of the
+    /// cluster for `neo4j` uris
+    pub async fn execute_as(&self, q: Query, mode: AccessMode) -> Result<RowStream> {
+        self.execute_with(q, TxnConfig::new().mode(mode)).await
+    }
+
+    /// Executes a query like [`Graph::execute`] in an auto-commit transaction with a timeout,
+    /// metadata, access mode, database or bookmarks, see [`TxnConfig`]
+    pub async fn execute_with(&self, q: Query, txn_config: TxnConfig) -> Result<RowStream> {
+        let (q, config, mode) = self.auto_commit(q, txn_config)?;
+        let sent = q.sent_bookmarks().to_vec();
+        let connection = self.pool.get(mode, &config, &sent).await?;
+        let address = server_address(&connection);
+        let result = q.execute(&config, Arc::new(Mutex::new(connection))).await;
+        let mut stream = self.pool.check(address, result).await?;
+        if let Some(manager) = &self.config.bookmark_manager {
+            stream.track_bookmarks(manager.clone(), sent);
+        }
+        Ok(stream)
+    }
+
+    pub(crate) async fn run_on(&self, q: Query, txn_config: TxnConfig) -> Result<ResultSummary> {
+        let (q, config, mode) = self.auto_commit(q, txn_config)?;
+        let sent = q.sent_bookmarks().to_vec();
+        let connection = self.pool.get(mode, &config, &sent).await?;
+        let address = server_address(&connection);
+        let result = q.run(&config, Arc::new(Mutex::new(connection))).await;
+        let summary = self.pool.check(address, result).await?;
+        if let Some(manager) = &self.config.bookmark_manager {
+            manager.update(&sent, summary.bookmark.clone());
+        }
+        Ok(summary)
+    }
+
+    /// Puts the options of the auto-commit transaction into the RUN extra map, the query starts
+    /// from the bookmarks of the bookmark manager
+    fn auto_commit(
+        &self,
+        mut q: Query,
+        mut txn_config: TxnConfig,
+    ) -> Result<(Query, Config, AccessMode)> {
+        if let Some(e) = txn_config.take_error() {
+            return Err(e);
+        }
+        if let Some(imp_user) = q.take_imp_user() {
+            txn_config = txn_config.impersonate(&imp_user);
+        }
+        let config = txn_config.apply(&self.config);
+        let mut q = q.extend_extra(txn_config.extra(&config));
+        q.add_bookmarks(txn_config.take_bookmarks());
+        if let Some(manager) = &self.config.bookmark_manager {
+            q.add_bookmarks(manager.bookmarks());
+        }
+        Ok((q, config, txn_config.access_mode()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bookmarks::BookmarkManager;
+    use crate::connection::tests::{failure, ignored, stub_server, success, success_with};
+    use crate::types::BoltMap;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn should_report_the_failed_query_of_a_batch() {
+        let mut bookmark = BoltMap::default();
+        bookmark.put("bookmark".into(), "b1".into());
+        let uri = stub_server(vec![
+            vec![success()],
+            vec![],
+            vec![],
+            vec![],
+            vec![],
+            vec![],
+            vec![
+                success(),
+                success_with(bookmark),
+                failure("Neo.ClientError.Schema.ConstraintValidationFailed"),
+                ignored(),
+                ignored(),
+                ignored(),
+            ],
+        ])
+        .await;
+        let manager = BookmarkManager::new();
+        let config = config()
+            .uri(&uri)
+            .user("user")
+            .password("pass")
+            .max_connections(1)
+            .bookmark_manager(manager.clone())
+            .build()
+            .unwrap();
+        let graph = Graph::connect(config).await.unwrap();
+
+        let result = graph
+            .run_batch(vec![
+                query("CREATE (:Person {id: 1})"),
+                query("CREATE (:Person {id: 1})"),
+                query("CREATE (:Person {id: 2})"),
+            ])
+            .await;
+
+        match result {
+            Err(Error::BatchFailed { index, error }) => {
+                assert_eq!(index, 1);
+                assert!(matches!(*error, Error::Neo4jError(_)));
+            }
+            result => panic!("expected the second query to fail, got {:?}", result),
+        }
+        assert_eq!(manager.bookmarks(), ["b1"]);
+    }
+
+    #[tokio::test]
+    async fn should_reject_batches_impersonating_different_users() {
+        let uri = stub_server(vec![vec![success()]]).await;
+        let config = config()
+            .uri(&uri)
+            .user("user")
+            .password("pass")
+            .build()
+            .unwrap();
+        let graph = Graph::connect(config).await.unwrap();
+
+        let result = graph
+            .run_batch(vec![
+                query("CREATE (:Person)").impersonate("alice"),
+                query("CREATE (:Person)").impersonate("alice"),
+                query("CREATE (:Person)"),
+            ])
+            .await;
+
+        assert!(matches!(result, Err(Error::MixedBatch { index: 2 })));
+    }
+
+    #[tokio::test]
+    async fn should_time_out_waiting_for_a_connection() {
+        let uri = stub_server(vec![vec![success()], vec![]]).await;
+        let config = config()
+            .uri(&uri)
+            .user("user")
+            .password("pass")
+            .max_connections(1)
+            .connection_acquisition_timeout(Duration::from_millis(50))
+            .build()
+            .unwrap();
+        let graph = Graph::connect(config).await.unwrap();
+        let _txn = graph.start_txn().await.unwrap();
+
+        assert!(matches!(
+            graph.run(query("RETURN 1")).await,
+            Err(Error::ConnectionAcquisitionTimeout)
+        ));
+    }
+
+    #[tokio::test]
+    async fn should_report_the_metrics_of_the_pool() {
+        let uri = stub_server(vec![vec![success()], vec![]]).await;
+        let config = config()
+            .uri(&uri)
+            .user("user")
+            .password("pass")
+            .max_connections(1)
+            .connection_acquisition_timeout(Duration::from_millis(50))
+            .build()
+            .unwrap();
+        let graph = Graph::connect(config).await.unwrap();
+        let _txn = graph.start_txn().await.unwrap();
+        assert!(graph.run(query("RETURN 1")).await.is_err());
+
+        let metrics = graph.metrics();
+
+        assert_eq!(metrics.len(), 1);
+        assert_eq!(metrics[0].address, uri);
+        assert_eq!((metrics[0].created, metrics[0].closed), (1, 0));
+        assert_eq!((metrics[0].in_use, metrics[0].idle), (1, 0));
+        assert_eq!(metrics[0].acquired, 1);
+        assert_eq!(metrics[0].acquisition_time.count(), 1);
+    }
+
+    #[tokio::test]
+    async fn should_report_unreachable_servers_on_connect() {
+        let uri = tokio::net::TcpListener::bind("127.0.0.1:0")
+            .await
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .to_string();
+        let settings = |min_idle| {
+            config()
+                .uri(&uri)
+                .user("user")
+                .password("pass")
+                .min_idle(min_idle)
+                .build()
+                .unwrap()
+        };
+
+        assert!(Graph::connect(settings(1)).await.is_err());
+        assert!(Graph::connect(settings(0)).await.is_ok());
     }
 }
@@ -1,8 +1,8 @@
-//! Neo4j driver compatible with neo4j 4.x versions
+//! Neo4j driver compatible with neo4j 4.x and 5.x versions
 //!
 //! * An implementation of the [bolt protocol][bolt] to interact with Neo4j server
 //! * async/await apis using [tokio][tokio]
-//! * Supports bolt 4.2 specification
+//! * Supports bolt 4.0 - 4.4 and 5.0 - 5.4 specifications
 //! * tested with Neo4j versions: 4.0, 4.1, 4.2
 //!
 //!
@@ -58,6 +58,16 @@
 //! Use the config builder to override the default configurations like
 //! * `fetch_size` - number of rows to fetch in batches (default is 200)
 //! * `max_connections` - maximum size of the connection pool (default is 16)
+//! * `max_connection_lifetime` - connections older than this are replaced (default is 1 hour)
+//! * `idle_time_before_connection_test` - idle connections are tested before they are used
+//!   (not tested by default)
+//! * `max_connection_idle_time` - idle connections are closed in the background (kept by
+//!   default)
+//! * `min_idle` - connections opened by [`Graph::connect`], which fails if the server can't be
+//!   reached, to each reader and writer for `neo4j` uris (default is 1)
+//! * `connection_acquisition_timeout` - how long to wait for a connection when all are in use
+//!   (default is 60 seconds)
+//! * `connect_timeout` - how long opening a connection may take (default is 30 seconds)
 //! * `db` - the database to connect to (default is `neo4j`)
 //!
 //! ```no_run
@@ -84,6 +94,131 @@
 //! }
 //! ```
 //!
+//! ## Encryption
+//!
+//! The scheme of the uri decides whether the connection is encrypted:
+//! * `bolt://` and `neo4j://` - unencrypted, the default for a bare `host:port`
+//! * `bolt+s://` and `neo4j+s://` - TLS, the server certificate must be signed by a trusted CA
+//! * `bolt+ssc://` and `neo4j+ssc://` - TLS, self-signed server certificates are accepted
+//!
+//! Encryption uses rustls and requires the optional `rustls` feature, without it encrypted
+//! schemes are rejected with [`Error::UnsupportedScheme`].
+//! Additional CA certificates and a client certificate can be configured with the config builder.
+//!
+//! ```no_run
+//! use neo4rs::*;
+//!
+//! #[tokio::main]
+//! async fn main() {
+//!    let config = config()
+//!        .uri("neo4j+s://localhost:7687")
+//!        .user("neo4j")
+//!        .password("neo")
+//!        .ca_certificate("/etc/neo4j/ca.pem")
+//!        .client_certificate("/etc/neo4j/client.pem", "/etc/neo4j/client.key")
+//!        .build()
+//!        .unwrap();
+//!    let graph = Graph::connect(config).await.unwrap();
+//!    graph.run(query("RETURN 1")).await.unwrap();
+//! }
+//! ```
+//!
+//! ## Authentication
+//!
+//! [`ConfigBuilder::user`] and [`ConfigBuilder::password`] authenticate with the `basic` scheme,
+//! other schemes are configured with an [`AuthToken`]: a bearer token issued by a single sign-on
+//! provider, a kerberos ticket, no credentials for servers with authentication disabled, or
+//! the credentials of a custom authentication plugin.
+//!
+//! ```no_run
+//! use neo4rs::*;
+//!
+//! #[tokio::main]
+//! async fn main() {
+//!    let config = config()
+//!        .uri("neo4j+s://localhost:7687")
+//!        .auth(AuthToken::bearer("eyJhbGciOiJSUzI1NiJ9..."))
+//!        .build()
+//!        .unwrap();
+//!    let graph = Graph::connect(config).await.unwrap();
+//!    graph.run(query("RETURN 1")).await.unwrap();
+//! }
+//! ```
+//!
+//! Tokens which expire are provided by an [`AuthTokenManager`] instead. New connections ask it
+//! for the current token, and it is told when the server rejected a token with
+//! `Neo.ClientError.Security.TokenExpired`. Pooled connections with an outdated token
+//! authenticate again on bolt 5.1 or later, and are replaced on older versions.
+//!
+//! ```no_run
+//! use neo4rs::*;
+//! use async_trait::async_trait;
+//! use std::sync::Mutex;
+//!
+//! struct SingleSignOn {
+//!     token: Mutex<Option<AuthToken>>,
+//! }
+//!
+//! #[async_trait]
+//! impl AuthTokenManager for SingleSignOn {
+//!     async fn get_token(&self) -> Result<AuthToken> {
+//!         let mut token = self.token.lock().unwrap();
+//!         Ok(token
+//!             .get_or_insert_with(|| AuthToken::bearer("a new token from the provider"))
+//!             .clone())
+//!     }
+//!
+//!     async fn on_token_expired(&self, expired: &AuthToken) {
+//!         let mut token = self.token.lock().unwrap();
+//!         if token.as_ref() == Some(expired) {
+//!             *token = None;
+//!         }
+//!     }
+//! }
+//!
+//! #[tokio::main]
+//! async fn main() {
+//!    let config = config()
+//!        .uri("neo4j+s://localhost:7687")
+//!        .auth_manager(SingleSignOn { token: Mutex::new(None) })
+//!        .build()
+//!        .unwrap();
+//!    let graph = Graph::connect(config).await.unwrap();
+//!    graph.run(query("RETURN 1")).await.unwrap();
+//! }
+//! ```
+//!
+//! ## Cluster routing
+//!
+//! With a `neo4j://` uri the driver fetches the routing table of the database from the cluster
+//! and keeps a connection pool per member. [`Graph::run`], [`Graph::execute`] and
+//! [`Graph::start_txn`] are sent to the leader, reads can be spread over the followers with
+//! [`Graph::run_as`], [`Graph::execute_as`] and [`Graph::start_txn_as`]. Routing tables are
+//! refreshed when their ttl expires, and members which can't be reached or lost the leadership
+//! are skipped until then.
+//!
+//! ```no_run
+//! use neo4rs::*;
+//! use futures::stream::*;
+//!
+//! #[tokio::main]
+//! async fn main() {
+//!    let graph = Graph::new("neo4j://localhost:7687", "neo4j", "neo").await.unwrap();
+//!    graph.run(query("CREATE (p:Person {name: 'Mark'})")).await.unwrap();
+//!
+//!    let q = query("MATCH (p:Person) RETURN p.name AS name");
+//!    let mut result = graph.execute_as(q, AccessMode::Read).await.unwrap();
+//!    while let Ok(Some(row)) = result.next().await {
+//!        let name: String = row.get("name").unwrap();
+//!        println!("{}", name);
+//!    }
+//!
+//!    let txn = graph.start_txn_as(AccessMode::Read).await.unwrap();
+//!    txn.run(query("MATCH (p:Person) RETURN count(p)")).await.unwrap();
+//!    txn.commit().await.unwrap();
+//! }
+//! ```
+//!
 //! ## Nodes
 //! A simple example to create a node and consume the created node from the row stream.
 //!
@@ -162,7 +297,9 @@
 //! ### Streams within a transaction
 //!
 //! Each [`RowStream`] returned by various execute within the same transaction are well isolated,
-//! so you can consume the stream anytime within the transaction using [`RowStream::next`]
+//! so you can consume the stream anytime within the transaction using [`RowStream::next`]. The
+//! responses of the server are matched to the streams by their query id, rows which arrive while
+//! another query of the transaction waits for its response are buffered.
 //!
 //!
 //! ```no_run
@@ -219,6 +356,11 @@
 //!
 //!
 //! ### Rollback a transaction
+//!
+//! A transaction which is dropped without being committed is rolled back. Once a query of a
+//! transaction failed, see [`Txn::state`], further queries are rejected with
+//! [`Error::InvalidTransactionState`] and the transaction can only be rolled back.
+//!
 //! ```no_run
 //! use neo4rs::*;
 //! use futures::stream::*;
@@ -299,6 +441,225 @@
 //!
 //! ```
 //!
+//! ## Batches
+//!
+//! [`Graph::run_batch`] and [`Txn::run_batch`] send many queries before waiting for the server,
+//! saving a round trip per query, and return the [`ResultSummary`] of each. When a query fails,
+//! the error is an [`Error::BatchFailed`] with the index of the failed query.
+//!
+//! ```no_run
+//! use neo4rs::*;
+//!
+//! #[tokio::main]
+//! async fn main() {
+//!    let graph = Graph::new("127.0.0.1:7687", "neo4j", "neo").await.unwrap();
+//!    let txn = graph.start_txn().await.unwrap();
+//!    let queries = (0..100)
+//!        .map(|id| query("CREATE (:Item {id: $id})").param("id", id))
+//!        .collect();
+//!    match txn.run_batch(queries).await {
+//!        Ok(summaries) => {
+//!            println!("ran {} queries", summaries.len());
+//!            txn.commit().await.unwrap();
+//!        }
+//!        Err(Error::BatchFailed { index, error }) => {
+//!            println!("query {} failed: {}", index, error);
+//!            txn.rollback().await.unwrap();
+//!        }
+//!        Err(e) => panic!("{}", e),
+//!    }
+//! }
+//! ```
+//!
+//! ## Transaction functions
+//!
+//! [`Graph::execute_write`] and [`Graph::execute_read`] run a function in a transaction and
+//! commit it, transactions failing with a transient error like a deadlock or a leader switch are
+//! rolled back and retried with exponential backoff until [`ConfigBuilder::max_retry_time`] is
+//! used up. The function may be called several times, so it should only change the database.
+//!
+//! ```no_run
+//! use neo4rs::*;
+//!
+//! #[tokio::main]
+//! async fn main() {
+//!    let graph = Graph::new("127.0.0.1:7687", "neo4j", "neo").await.unwrap();
+//!    let count = graph
+//!        .execute_write(|txn| async move {
+//!            txn.run(query("MERGE (c:Counter) SET c.count = coalesce(c.count, 0) + 1"))
+//!                .await?;
+//!            let mut result = txn.execute(query("MATCH (c:Counter) RETURN c.count AS count")).await?;
+//!            let row = result.next().await?.unwrap();
+//!            Ok(row.get::<i64>("count").unwrap())
+//!        })
+//!        .await
+//!        .unwrap();
+//!    println!("counted {}", count);
+//! }
+//! ```
+//!
+//! ## Transaction configuration
+//!
+//! A [`TxnConfig`] sets the timeout, metadata, access mode, database and bookmarks of a
+//! transaction started with [`Graph::start_txn_with`], or of the auto-commit transaction of a
+//! query run with [`Graph::run_with`] or [`Graph::execute_with`]. The metadata is shown by
+//! `SHOW TRANSACTIONS` and in the query log.
+//!
+//! ```no_run
+//! use neo4rs::*;
+//! use std::time::Duration;
+//!
+//! #[tokio::main]
+//! async fn main() {
+//!    let graph = Graph::new("127.0.0.1:7687", "neo4j", "neo").await.unwrap();
+//!    let txn = graph
+//!        .start_txn_with(
+//!            TxnConfig::new()
+//!                .timeout(Duration::from_secs(10))
+//!                .metadata("app", "billing"),
+//!        )
+//!        .await
+//!        .unwrap();
+//!    txn.run(query("CREATE (i:Invoice)")).await.unwrap();
+//!    txn.commit().await.unwrap();
+//!
+//!    let config = TxnConfig::new().mode(AccessMode::Read).db("movies");
+//!    let mut result = graph
+//!        .execute_with(query("MATCH (m:Movie) RETURN m"), config)
+//!        .await
+//!        .unwrap();
+//!    while let Ok(Some(row)) = result.next().await {
+//!        println!("{:?}", row.get::<Node>("m"));
+//!    }
+//! }
+//! ```
+//!
+//! A service account with the privilege to impersonate users can run transactions and queries
+//! with the privileges of another user, through [`TxnConfig::impersonate`], [`Query::impersonate`]
+//! or [`SessionConfig::impersonated_user`]. Impersonation requires Neo4j 4.4 or later, older
+//! servers fail with [`Error::UnsupportedVersion`].
+//!
+//! ```no_run
+//! use neo4rs::*;
+//!
+//! #[tokio::main]
+//! async fn main() {
+//!    let graph = Graph::new("neo4j://127.0.0.1:7687", "service", "pass").await.unwrap();
+//!    let q = query("MATCH (d:Document) RETURN d").impersonate("alice");
+//!    let mut result = graph.execute(q).await.unwrap();
+//!    while let Ok(Some(row)) = result.next().await {
+//!        println!("{:?}", row.get::<Node>("d"));
+//!    }
+//! }
+//! ```
+//!
+//! ## Bookmarks
+//!
+//! A transaction started on another connection, or another member of a cluster, doesn't
+//! necessarily see what was just committed. Bookmarks returned by
+//! [`Txn::commit_with_bookmark`] and in the [`ResultSummary`] of auto-commit queries make the
+//! server wait until it caught up with those changes, they are passed to
+//! [`Graph::start_txn_with_bookmarks`] or [`Query::bookmarks`]. A [`BookmarkManager`] does this
+//! automatically for all transactions and queries of a graph.
+//!
+//! ```no_run
+//! use neo4rs::*;
+//!
+//! #[tokio::main]
+//! async fn main() {
+//!    let manager = BookmarkManager::new();
+//!    let config = config()
+//!        .uri("neo4j://localhost:7687")
+//!        .user("neo4j")
+//!        .password("neo")
+//!        .bookmark_manager(manager.clone())
+//!        .build()
+//!        .unwrap();
+//!    let graph = Graph::connect(config).await.unwrap();
+//!    graph.run(query("CREATE (p:Person {name: 'Mark'})")).await.unwrap();
+//!    // sees Mark even if it runs on another member of the cluster
+//!    let mut result = graph
+//!        .execute_as(query("MATCH (p:Person) RETURN p"), AccessMode::Read)
+//!        .await
+//!        .unwrap();
+//!    assert!(result.next().await.unwrap().is_some());
+//!
+//!    let txn = graph.start_txn().await.unwrap();
+//!    txn.run(query("CREATE (p:Person {name: 'Jane'})")).await.unwrap();
+//!    let bookmark = txn.commit_with_bookmark().await.unwrap();
+//!    let txn = graph
+//!        .start_txn_with_bookmarks(bookmark.into_iter().collect())
+//!        .await
+//!        .unwrap();
+//!    txn.commit().await.unwrap();
+//! }
+//! ```
+//!
+//! ## Sessions
+//!
+//! A [`Session`] runs queries and transactions with its own defaults: the database, the access
+//! mode, the fetch size and the user to impersonate. Sessions share the connection pool of the
+//! graph, so one graph can serve several databases, and each session chains the bookmarks of its
+//! work so that it always sees its own changes.
+//!
+//! ```no_run
+//! use neo4rs::*;
+//!
+//! #[tokio::main]
+//! async fn main() {
+//!    let graph = Graph::new("neo4j://localhost:7687", "neo4j", "neo").await.unwrap();
+//!    let movies = graph.session(SessionConfig {
+//!        db: Some("movies".to_owned()),
+//!        ..SessionConfig::default()
+//!    });
+//!    movies.run(query("CREATE (m:Movie {title: 'Heat'})")).await.unwrap();
+//!
+//!    let reader = graph.session(SessionConfig {
+//!        db: Some("movies".to_owned()),
+//!        access_mode: AccessMode::Read,
+//!        bookmarks: movies.last_bookmarks(),
+//!        ..SessionConfig::default()
+//!    });
+//!    let mut result = reader.execute(query("MATCH (m:Movie) RETURN m")).await.unwrap();
+//!    assert!(result.next().await.unwrap().is_some());
+//! }
+//! ```
+//!
+//! ## Pool metrics
+//!
+//! [`Graph::metrics`] returns the state of the connection pool of each server: how many
+//! connections were opened, closed or failed to open, how many are in use or idle, how often
+//! acquiring a connection had to wait and how long it took. With the `metrics` feature the same
+//! numbers are recorded with the [`metrics`](https://docs.rs/metrics) crate, as counters, gauges
+//! and a histogram named `neo4rs_*` and labelled with the `address` of the server, so that any
+//! installed recorder, like a Prometheus exporter, picks them up.
+//!
+//! ```no_run
+//! use neo4rs::*;
+//!
+//! #[tokio::main]
+//! async fn main() {
+//!    let graph = Graph::new("127.0.0.1:7687", "neo4j", "neo").await.unwrap();
+//!    graph.run(query("RETURN 1")).await.unwrap();
+//!    for pool in graph.metrics() {
+//!        println!(
+//!            "{}: {} in use, {} idle, {} acquired",
+//!            pool.address, pool.in_use, pool.idle, pool.acquired
+//!        );
+//!    }
+//! }
+//! ```
+//!
+//! ## Tracing
+//!
+//! With the `tracing` feature the driver emits [`tracing`](https://docs.rs/tracing) spans for
+//! connecting, the bolt handshake, HELLO, acquiring a connection from the pool, RUN, each batch
+//! of PULL and BEGIN, COMMIT and ROLLBACK. The spans are created in the span of the caller and
+//! their fields follow the OpenTelemetry conventions for databases, e.g. `db.namespace`,
+//! `db.query.text`, `server.address`, `db.response.returned_rows` and, for failures,
+//! `db.response.status_code` with the Neo4j error code, plus `neo4j.qid` for the query id, so
+//! that `tracing-opentelemetry` can export them to a distributed trace.
+//!
 //! ## Relationships
 //!
 //! Bounded Relationship between nodes are created using cypher queries and the same can be parsed
@@ -749,26 +1110,211 @@
 //! }
 //! ```
 //!
+//! ## Deserialization
+//!
+//! Rows, nodes and relationships can be deserialized into any type implementing
+//! `serde::Deserialize` with [`Row::to`], [`Node::to`] and [`Relation::to`]. Errors name the
+//! offending field, see [`DeError`].
+//!
+//! ```no_run
+//! use neo4rs::*;
+//! use serde::Deserialize;
+//!
+//! #[derive(Deserialize)]
+//! struct Person {
+//!     name: String,
+//!     born: chrono::NaiveDate,
+//!     nickname: Option<String>,
+//! }
+//!
+//! #[tokio::main]
+//! async fn main() {
+//!    let uri = "127.0.0.1:7687";
+//!    let user = "neo4j";
+//!    let pass = "neo";
+//!    let graph = Graph::new(uri, user, pass).await.unwrap();
+//!
+//!    let mut result = graph
+//!        .execute(query("MATCH (p:Person) RETURN p"))
+//!        .await
+//!        .unwrap();
+//!    while let Ok(Some(row)) = result.next().await {
+//!        let node: Node = row.get("p").unwrap();
+//!        let person: Person = node.to().unwrap();
+//!        println!("{} was born on {}", person.name, person.born);
+//!    }
+//! }
+//! ```
+//!
+//! ## Serialization
+//!
+//! Parameters can be any type implementing `serde::Serialize`, either one parameter at a time
+//! with [`Query::param`] taking a reference to a `Vec` or `HashMap`, or all fields of a struct at
+//! once with [`Query::params`]. Chrono dates, times and datetimes, `DateTime<Utc>` included, are
+//! sent as bolt temporal values, strings are always sent as strings. The [`temporal`] modules
+//! choose the temporal type of a field explicitly.
+//!
+//! ```no_run
+//! use neo4rs::*;
+//! use serde::Serialize;
+//!
+//! #[derive(Serialize)]
+//! struct Person {
+//!     name: String,
+//!     born: chrono::NaiveDate,
+//! }
+//!
+//! #[derive(Serialize)]
+//! struct Params {
+//!     name: String,
+//! }
+//!
+//! #[tokio::main]
+//! async fn main() {
+//!    let uri = "127.0.0.1:7687";
+//!    let user = "neo4j";
+//!    let pass = "neo";
+//!    let graph = Graph::new(uri, user, pass).await.unwrap();
+//!
+//!    let people = vec![Person {
+//!        name: "Mark".into(),
+//!        born: chrono::NaiveDate::from_ymd_opt(1990, 5, 12).unwrap(),
+//!    }];
+//!    graph
+//!        .run(query("UNWIND $rows AS row CREATE (p:Person) SET p = row").param("rows", &people))
+//!        .await
+//!        .unwrap();
+//!
+//!    let params = Params { name: "Mark".into() };
+//!    graph
+//!        .run(query("MATCH (p:Person { name: $name }) DELETE p").params(&params))
+//!        .await
+//!        .unwrap();
+//! }
+//! ```
+//!
+//! ## Streams
+//!
+//! A [`RowStream`] can be turned into a [`futures::Stream`] with [`RowStream::into_stream`], or
+//! into a stream of your own types with [`RowStream::into_stream_as`].
+//!
+//! ```no_run
+//! use neo4rs::*;
+//! use futures::stream::*;
+//!
+//! #[tokio::main]
+//! async fn main() {
+//!    let uri = "127.0.0.1:7687";
+//!    let user = "neo4j";
+//!    let pass = "neo";
+//!    let graph = Graph::new(uri, user, pass).await.unwrap();
+//!
+//!    let rows: Vec<Row> = graph
+//!        .execute(query("UNWIND range(1, 10) AS n RETURN n"))
+//!        .await
+//!        .unwrap()
+//!        .into_stream()
+//!        .try_collect()
+//!        .await
+//!        .unwrap();
+//!    assert_eq!(rows.len(), 10);
+//! }
+//! ```
+//!
+//! ## Result summary
+//!
+//! [`Graph::run_with_summary`] and [`RowStream::finish`] return a [`ResultSummary`] with the
+//! counters of what a query changed, the query type, server timings and notifications.
+//!
+//! ```no_run
+//! use neo4rs::*;
+//!
+//! #[tokio::main]
+//! async fn main() {
+//!    let uri = "127.0.0.1:7687";
+//!    let user = "neo4j";
+//!    let pass = "neo";
+//!    let graph = Graph::new(uri, user, pass).await.unwrap();
+//!
+//!    let summary = graph
+//!        .run_with_summary(query("CREATE (p:Person)-[:KNOWS]->(:Person)"))
+//!        .await
+//!        .unwrap();
+//!    assert_eq!(summary.counters.nodes_created, 2);
+//!    assert_eq!(summary.counters.relationships_created, 1);
+//!
+//!    let mut result = graph.execute(query("MATCH (p:Person) RETURN p")).await.unwrap();
+//!    let _first = result.next().await.unwrap();
+//!    let summary = result.finish().await.unwrap();
+//!    assert_eq!(summary.query_type, Some(QueryType::ReadOnly));
+//! }
+//! ```
+//!
+//! ## Errors
+//!
+//! Failures reported by the server are returned as [`Error::Neo4jError`], which carries the
+//! Neo4j status code split into classification, category and title.
+//!
+//! ```no_run
+//! use neo4rs::*;
+//!
+//! #[tokio::main]
+//! async fn main() {
+//!    let uri = "127.0.0.1:7687";
+//!    let user = "neo4j";
+//!    let pass = "neo";
+//!    let graph = Graph::new(uri, user, pass).await.unwrap();
+//!
+//!    let error = match graph.execute(query("RETRUN 1")).await {
+//!        Err(Error::Neo4jError(e)) => e,
+//!        _ => panic!("expected a server error"),
+//!    };
+//!    assert_eq!(error.code, "Neo.ClientError.Statement.SyntaxError");
+//!    assert_eq!(error.category, "Statement");
+//!    assert!(error.is_client_error());
+//!    assert!(!error.is_transient());
+//! }
+//! ```
+//!
 //!
+mod auth;
+mod bookmarks;
 mod config;
 mod connection;
 mod convert;
+mod de;
 mod errors;
 mod graph;
 mod messages;
+mod metrics;
 mod pool;
 mod query;
+mod retry;
+mod routing;
 mod row;
+mod ser;
+mod session;
 mod stream;
+mod summary;
+mod trace;
+mod transport;
 mod txn;
 mod types;
+mod uri;
 mod version;
 
+pub use crate::auth::{AuthToken, AuthTokenManager};
+pub use crate::bookmarks::BookmarkManager;
 pub use crate::config::{config, Config, ConfigBuilder};
 pub use crate::errors::*;
 pub use crate::graph::{query, Graph};
+pub use crate::metrics::{Histogram, PoolMetrics};
 pub use crate::query::Query;
+pub use crate::routing::AccessMode;
 pub use crate::row::{Node, Path, Point2D, Point3D, Relation, Row, UnboundedRelation};
+pub use crate::ser::temporal;
+pub use crate::session::{Session, SessionConfig};
 pub use crate::stream::RowStream;
-pub use crate::txn::Txn;
+pub use crate::summary::{Counters, InputPosition, Notification, QueryType, ResultSummary};
+pub use crate::txn::{Txn, TxnConfig, TxnState};
 pub use crate::version::Version;
@@ -4,10 +4,14 @@ mod commit;
 mod discard;
 mod failure;
 mod hello;
+mod ignored;
+mod logoff;
+mod logon;
 mod pull;
 mod record;
 mod reset;
 mod rollback;
+mod route;
 mod run;
 mod success;
 use crate::errors::*;
@@ -19,25 +23,32 @@ use commit::Commit;
 use discard::Discard;
 use failure::Failure;
 use hello::Hello;
+use ignored::Ignored;
+use logoff::Logoff;
+use logon::Logon;
 use pull::Pull;
 use record::Record;
 use reset::Reset;
 use rollback::Rollback;
+use route::Route;
 use run::Run;
 use std::cell::RefCell;
 use std::rc::Rc;
-use success::Success;
+pub use success::Success;
 
 #[derive(Debug, PartialEq, Clone)]
 pub enum BoltResponse {
     Success(Success),
     Failure(Failure),
+    Ignored(Ignored),
     Record(Record),
 }
 
 #[derive(Debug, PartialEq, Clone)]
 pub enum BoltRequest {
     Hello(Hello),
+    Logon(Logon),
+    Logoff(Logoff),
     Run(Run),
     Pull(Pull),
     Discard(Discard),
@@ -45,32 +56,69 @@ pub enum BoltRequest {
     Commit(Commit),
     Rollback(Rollback),
     Reset(Reset),
+    Route(Route),
 }
 
 impl BoltRequest {
-    pub fn hello(agent: &str, principal: String, credentials: String) -> BoltRequest {
+    /// From bolt 5.1 the credentials are not part of HELLO and have to be sent with
+    /// [`BoltRequest::logon`] instead.
+    ///
+    /// `routing` is the routing context of `neo4j` uris, it tells the server that the driver
+    /// routes queries itself.
+    pub fn hello(
+        version: Version,
+        agent: &str,
+        auth: BoltMap,
+        routing: Option<BoltMap>,
+    ) -> BoltRequest {
         let mut data = BoltMap::default();
         data.put("user_agent".into(), agent.into());
-        data.put("scheme".into(), "basic".into());
-        data.put("principal".into(), principal.into());
-        data.put("credentials".into(), credentials.into());
+        if let Some(routing) = routing {
+            data.put("routing".into(), BoltType::Map(routing));
+        }
+        if version.supports_utc_patch() {
+            data.put("patch_bolt".into(), vec!["utc"].into());
+        }
+        if version.requires_bolt_agent() {
+            let mut bolt_agent = BoltMap::default();
+            bolt_agent.put(
+                "product".into(),
+                format!("neo4rs/{}", env!("CARGO_PKG_VERSION")).into(),
+            );
+            data.put("bolt_agent".into(), BoltType::Map(bolt_agent));
+        }
+        if !version.supports_logon() {
+            data.value.extend(auth.value);
+        }
         BoltRequest::Hello(Hello::new(data))
     }
 
-    pub fn run(db: &str, query: &str, params: BoltMap) -> BoltRequest {
-        BoltRequest::Run(Run::new(db.into(), query.into(), params))
+    pub fn logon(auth: BoltMap) -> BoltRequest {
+        BoltRequest::Logon(Logon::new(auth))
+    }
+
+    /// Ends the authentication of a connection, which can then be authenticated again with
+    /// [`BoltRequest::logon`] (bolt 5.1+)
+    pub fn logoff() -> BoltRequest {
+        BoltRequest::Logoff(Logoff::new())
+    }
+
+    pub fn run(db: &str, query: &str, params: BoltMap, extra: BoltMap) -> BoltRequest {
+        let mut run = Run::new(db.into(), query.into(), params);
+        run.extend_extra(extra);
+        BoltRequest::Run(run)
     }
 
     pub fn pull(n: usize, qid: i64) -> BoltRequest {
         BoltRequest::Pull(Pull::new(n as i64, qid))
     }
 
-    pub fn discard() -> BoltRequest {
-        BoltRequest::Discard(Discard::default())
+    pub fn discard(qid: i64) -> BoltRequest {
+        BoltRequest::Discard(Discard::new(-1, qid))
     }
 
-    pub fn begin() -> BoltRequest {
-        BoltRequest::Begin(Begin::new(BoltMap::default()))
+    pub fn begin(extra: BoltMap) -> BoltRequest {
+        BoltRequest::Begin(Begin::new(extra))
     }
 
     pub fn commit() -> BoltRequest {
@@ -84,12 +132,56 @@ impl BoltRequest {
     pub fn reset() -> BoltRequest {
         BoltRequest::Reset(Reset::new())
     }
+
+    /// Requests the routing table of `db`, an empty name means the default database of the
+    /// user, or of the impersonated user if there is one. The server waits for the `bookmarks`
+    /// before answering, so that a database created by them is known.
+    pub fn route(
+        version: Version,
+        routing: BoltMap,
+        bookmarks: &[String],
+        db: &str,
+        imp_user: Option<&str>,
+    ) -> BoltRequest {
+        let db = if version >= Version::V4_4 || imp_user.is_some() {
+            let mut extra = BoltMap::default();
+            if !db.is_empty() {
+                extra.put("db".into(), db.into());
+            }
+            if let Some(imp_user) = imp_user {
+                extra.put("imp_user".into(), imp_user.into());
+            }
+            BoltType::Map(extra)
+        } else if db.is_empty() {
+            BoltType::Null(BoltNull)
+        } else {
+            db.into()
+        };
+        let bookmarks: Vec<BoltType> = bookmarks.iter().map(|b| b.as_str().into()).collect();
+        BoltRequest::Route(Route::new(routing, bookmarks.into(), db))
+    }
 }
 
 impl BoltRequest {
     pub fn into_bytes(self, version: Version) -> Result<Bytes> {
+        if self.has_imp_user() && !version.supports_impersonation() {
+            return Err(Error::UnsupportedVersion(format!(
+                "impersonation requires bolt 4.4 or later, negotiated {:?}",
+                version
+            )));
+        }
+
+        if matches!(self, BoltRequest::Route(_)) && !version.supports_route() {
+            return Err(Error::UnsupportedVersion(format!(
+                "cluster routing requires bolt 4.3 or later, negotiated {:?}",
+                version
+            )));
+        }
+
         let bytes: Bytes = match self {
             BoltRequest::Hello(hello) => hello.into_bytes(version)?,
+            BoltRequest::Logon(logon) => logon.into_bytes(version)?,
+            BoltRequest::Logoff(logoff) => logoff.into_bytes(version)?,
             BoltRequest::Run(run) => run.into_bytes(version)?,
             BoltRequest::Pull(pull) => pull.into_bytes(version)?,
             BoltRequest::Discard(discard) => discard.into_bytes(version)?,
@@ -97,9 +189,21 @@ impl BoltRequest {
             BoltRequest::Commit(commit) => commit.into_bytes(version)?,
             BoltRequest::Rollback(rollback) => rollback.into_bytes(version)?,
             BoltRequest::Reset(reset) => reset.into_bytes(version)?,
+            BoltRequest::Route(route) => route.into_bytes(version)?,
         };
         Ok(bytes)
     }
+
+    fn has_imp_user(&self) -> bool {
+        match self {
+            BoltRequest::Run(run) => run.extra().contains_key("imp_user"),
+            BoltRequest::Begin(begin) => begin.extra().contains_key("imp_user"),
+            BoltRequest::Route(route) => {
+                matches!(route.db(), BoltType::Map(extra) if extra.contains_key("imp_user"))
+            }
+            _ => false,
+        }
+    }
 }
 
 impl BoltResponse {
@@ -111,6 +215,9 @@ impl BoltResponse {
             input if Failure::can_parse(version, input.clone()) => {
                 Ok(BoltResponse::Failure(Failure::parse(version, input)?))
             }
+            input if Ignored::can_parse(version, input.clone()) => {
+                Ok(BoltResponse::Ignored(Ignored::parse(version, input)?))
+            }
             input if Record::can_parse(version, input.clone()) => {
                 Ok(BoltResponse::Record(Record::parse(version, input)?))
             }
@@ -118,3 +225,156 @@ impl BoltResponse {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::AuthToken;
+
+    #[test]
+    fn should_reject_imp_user_before_bolt_4_4() {
+        let extra: BoltMap = vec![("imp_user".into(), "alice".into())]
+            .into_iter()
+            .collect();
+
+        let begin = BoltRequest::Begin(Begin::new(extra.clone()));
+        assert!(matches!(
+            begin.into_bytes(Version::V4_3),
+            Err(Error::UnsupportedVersion(_))
+        ));
+
+        let begin = BoltRequest::Begin(Begin::new(extra));
+        assert!(begin.into_bytes(Version::V4_4).is_ok());
+
+        let route = BoltRequest::route(Version::V4_3, BoltMap::default(), &[], "", Some("alice"));
+        assert!(matches!(
+            route.into_bytes(Version::V4_3),
+            Err(Error::UnsupportedVersion(_))
+        ));
+
+        let route = BoltRequest::route(Version::V4_4, BoltMap::default(), &[], "", Some("alice"));
+        let extra: BoltMap = vec![("imp_user".into(), "alice".into())]
+            .into_iter()
+            .collect();
+        let expected = Route::new(BoltMap::default(), BoltList::new(), BoltType::Map(extra));
+        assert_eq!(route, BoltRequest::Route(expected));
+    }
+
+    #[test]
+    fn should_offer_the_utc_patch_on_bolt_4_3_and_4_4() {
+        let hello = |version| BoltRequest::hello(version, "neo4rs", BoltMap::default(), None);
+        let mut expected = BoltMap::default();
+        expected.put("user_agent".into(), "neo4rs".into());
+        let legacy = BoltRequest::Hello(Hello::new(expected.clone()));
+        expected.put("patch_bolt".into(), vec!["utc"].into());
+        let patched = BoltRequest::Hello(Hello::new(expected));
+
+        assert_eq!(hello(Version::V4_2), legacy);
+        assert_eq!(hello(Version::V4_3), patched);
+        assert_eq!(hello(Version::V4_4), patched);
+        assert_eq!(hello(Version::V5_0), legacy);
+    }
+
+    #[test]
+    fn should_move_credentials_to_logon_from_bolt_5_1() {
+        let auth = AuthToken::basic("user", "pass").to_map();
+
+        let hello = BoltRequest::hello(Version::V5_0, "neo4rs", auth.clone(), None);
+        let mut expected = auth.clone();
+        expected.put("user_agent".into(), "neo4rs".into());
+        assert_eq!(hello, BoltRequest::Hello(Hello::new(expected)));
+
+        let hello = BoltRequest::hello(Version::V5_1, "neo4rs", auth.clone(), None);
+        let expected = vec![("user_agent".into(), "neo4rs".into())]
+            .into_iter()
+            .collect();
+        assert_eq!(hello, BoltRequest::Hello(Hello::new(expected)));
+
+        let logon = BoltRequest::logon(auth.clone());
+        assert_eq!(logon, BoltRequest::Logon(Logon::new(auth)));
+    }
+
+    #[test]
+    fn should_serialize_hello_without_credentials_from_bolt_5_1() {
+        let auth = AuthToken::basic("user", "pass").to_map();
+        let hello = BoltRequest::hello(Version::V5_1, "neo4rs", auth, None);
+
+        let bytes = hello.into_bytes(Version::V5_1).unwrap();
+
+        let mut expected = BytesMut::new();
+        expected.put_slice(&[0xB1, 0x01, map::TINY | 1, string::TINY | 10]);
+        expected.put_slice(b"user_agent");
+        expected.put_u8(string::TINY | 6);
+        expected.put_slice(b"neo4rs");
+        assert_eq!(bytes, expected.freeze());
+    }
+
+    #[test]
+    fn should_serialize_hello_with_the_bolt_agent_from_bolt_5_3() {
+        let auth = AuthToken::basic("user", "pass").to_map();
+        let hello = BoltRequest::hello(Version::V5_3, "neo4rs", auth, None);
+
+        let bytes = hello.into_bytes(Version::V5_3).unwrap();
+
+        assert_eq!(bytes[..3], [0xB1, 0x01, map::TINY | 2]);
+        let parsed = Hello::parse(Version::V5_3, Rc::new(RefCell::new(bytes))).unwrap();
+        let mut bolt_agent = BoltMap::default();
+        let product = format!("neo4rs/{}", env!("CARGO_PKG_VERSION"));
+        bolt_agent.put("product".into(), product.into());
+        let mut expected = BoltMap::default();
+        expected.put("user_agent".into(), "neo4rs".into());
+        expected.put("bolt_agent".into(), BoltType::Map(bolt_agent));
+        assert_eq!(parsed, Hello::new(expected));
+    }
+
+    #[test]
+    fn should_send_the_database_of_route_by_version() {
+        let route = BoltRequest::route(Version::V4_3, BoltMap::default(), &[], "movies", None);
+        let expected = Route::new(BoltMap::default(), BoltList::new(), "movies".into());
+        assert_eq!(route, BoltRequest::Route(expected));
+
+        let route = BoltRequest::route(Version::V4_3, BoltMap::default(), &[], "", None);
+        let expected = Route::new(
+            BoltMap::default(),
+            BoltList::new(),
+            BoltType::Null(BoltNull),
+        );
+        assert_eq!(route, BoltRequest::Route(expected));
+
+        let route = BoltRequest::route(Version::V4_4, BoltMap::default(), &[], "movies", None);
+        let extra: BoltMap = vec![("db".into(), "movies".into())].into_iter().collect();
+        let expected = Route::new(BoltMap::default(), BoltList::new(), BoltType::Map(extra));
+        assert_eq!(route, BoltRequest::Route(expected));
+
+        let bookmarks = ["bookmark-1".to_owned()];
+        let route = BoltRequest::route(Version::V4_4, BoltMap::default(), &bookmarks, "", None);
+        let expected = Route::new(
+            BoltMap::default(),
+            vec!["bookmark-1".into()].into(),
+            BoltType::Map(BoltMap::default()),
+        );
+        assert_eq!(route, BoltRequest::Route(expected));
+
+        let route = BoltRequest::route(Version::V4_2, BoltMap::default(), &[], "", None);
+        assert!(matches!(
+            route.into_bytes(Version::V4_2),
+            Err(Error::UnsupportedVersion(_))
+        ));
+    }
+
+    #[test]
+    fn should_serialize_run_on_all_versions() {
+        for version in [
+            Version::V4,
+            Version::V4_1,
+            Version::V4_2,
+            Version::V4_3,
+            Version::V4_4,
+            Version::V5_0,
+            Version::V5_4,
+        ] {
+            let run = BoltRequest::run("", "RETURN 1", BoltMap::default(), BoltMap::default());
+            assert!(run.into_bytes(version).is_ok());
+        }
+    }
+}
@@ -11,6 +11,10 @@ impl Begin {
     pub fn new(extra: BoltMap) -> Begin {
         Begin { extra }
     }
+
+    pub fn extra(&self) -> &BoltMap {
+        &self.extra
+    }
 }
 
 #[cfg(test)]
@@ -1,3 +1,4 @@
+use crate::errors::{Error, Neo4jError};
 use crate::types::*;
 use neo4rs_macros::BoltStruct;
 
@@ -11,6 +12,13 @@ impl Failure {
     pub fn get<T: std::convert::TryFrom<BoltType>>(&self, key: &str) -> Option<T> {
         self.metadata.get(key)
     }
+
+    pub fn into_error(self) -> Error {
+        Error::Neo4jError(Neo4jError::new(
+            self.get("code").unwrap_or_default(),
+            self.get("message").unwrap_or_default(),
+        ))
+    }
 }
 
 #[cfg(test)]
@@ -45,5 +53,11 @@ mod tests {
             failure.get::<String>("message").unwrap(),
             "The client is unauthorized due to authentication failure."
         );
+
+        let error = failure.into_error();
+        let error = error.as_neo4j_error().unwrap();
+        assert_eq!(error.code, "Neo.ClientError.Security.Unauthorized");
+        assert_eq!(error.category, "Security");
+        assert_eq!(error.title, "Unauthorized");
     }
 }
@@ -0,0 +1,21 @@
+use neo4rs_macros::BoltStruct;
+
+#[derive(Debug, PartialEq, Eq, Clone, BoltStruct)]
+#[signature(0xB0, 0x7E)]
+pub struct Ignored;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::version::Version;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    #[test]
+    fn should_deserialize_ignored() {
+        let data = Rc::new(RefCell::new(Bytes::from_static(&[0xB0, 0x7E])));
+
+        assert!(Ignored::can_parse(Version::V4_1, data.clone()));
+        assert_eq!(Ignored::parse(Version::V4_1, data).unwrap(), Ignored);
+    }
+}
@@ -0,0 +1,49 @@
+use crate::types::*;
+use neo4rs_macros::BoltStruct;
+
+#[derive(Debug, PartialEq, Clone, BoltStruct)]
+#[signature(0xB1, 0x6A)]
+pub struct Logon {
+    auth: BoltMap,
+}
+
+impl Logon {
+    pub fn new(auth: BoltMap) -> Logon {
+        Logon { auth }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::version::Version;
+    use bytes::*;
+
+    #[test]
+    fn should_serialize_logon() {
+        let logon = Logon::new(vec![("scheme".into(), "none".into())].into_iter().collect());
+
+        let bytes: Bytes = logon.into_bytes(Version::V5_1).unwrap();
+
+        assert_eq!(
+            bytes,
+            Bytes::from_static(&[
+                0xB1,
+                0x6A,
+                map::TINY | 1,
+                string::TINY | 6,
+                b's',
+                b'c',
+                b'h',
+                b'e',
+                b'm',
+                b'e',
+                string::TINY | 4,
+                b'n',
+                b'o',
+                b'n',
+                b'e',
+            ])
+        );
+    }
+}
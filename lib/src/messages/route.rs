@@ -0,0 +1,67 @@
+use crate::types::*;
+use neo4rs_macros::BoltStruct;
+
+#[derive(Debug, PartialEq, Clone, BoltStruct)]
+#[signature(0xB3, 0x66)]
+pub struct Route {
+    routing: BoltMap,
+    bookmarks: BoltList,
+    db: BoltType,
+}
+
+impl Route {
+    /// `db` is the database name, or null for the default database, in Bolt 4.3 and an extra
+    /// map with an optional `db` entry from Bolt 4.4
+    pub fn new(routing: BoltMap, bookmarks: BoltList, db: BoltType) -> Route {
+        Route {
+            routing,
+            bookmarks,
+            db,
+        }
+    }
+
+    pub fn db(&self) -> &BoltType {
+        &self.db
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::version::Version;
+    use bytes::*;
+
+    #[test]
+    fn should_serialize_route() {
+        let route = Route::new(
+            vec![("address".into(), "a:1".into())].into_iter().collect(),
+            BoltList::new(),
+            BoltType::Null(BoltNull),
+        );
+
+        let bytes: Bytes = route.into_bytes(Version::V4_3).unwrap();
+
+        assert_eq!(
+            bytes,
+            Bytes::from_static(&[
+                0xB3,
+                0x66,
+                map::TINY | 1,
+                string::TINY | 7,
+                b'a',
+                b'd',
+                b'd',
+                b'r',
+                b'e',
+                b's',
+                b's',
+                string::TINY | 3,
+                b'a',
+                b':',
+                b'1',
+                list::TINY,
+                0xC0,
+            ])
+        );
+    }
+}
@@ -19,6 +19,14 @@ impl Run {
                 .collect(),
         }
     }
+
+    pub fn extra(&self) -> &BoltMap {
+        &self.extra
+    }
+
+    pub fn extend_extra(&mut self, extra: BoltMap) {
+        self.extra.value.extend(extra.value);
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,254 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// The upper bounds of the acquisition time buckets
+const BUCKETS: [Duration; 8] = [
+    Duration::from_millis(1),
+    Duration::from_millis(5),
+    Duration::from_millis(10),
+    Duration::from_millis(50),
+    Duration::from_millis(100),
+    Duration::from_millis(500),
+    Duration::from_secs(1),
+    Duration::from_secs(5),
+];
+
+/// The state of the connection pool of one server, see [`crate::Graph::metrics`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PoolMetrics {
+    /// The `host:port` of the server
+    pub address: String,
+    /// Connections opened so far
+    pub created: u64,
+    /// Connections closed so far, because they broke, expired or were idle for too long
+    pub closed: u64,
+    /// Connections which could not be opened
+    pub failed_to_create: u64,
+    /// Connections used by queries and transactions right now
+    pub in_use: usize,
+    /// Connections waiting in the pool right now
+    pub idle: usize,
+    /// Connections handed out so far
+    pub acquired: u64,
+    /// Acquisitions which had to wait because all connections were in use
+    pub acquisition_waits: u64,
+    /// How long acquiring connections took
+    pub acquisition_time: Histogram,
+    /// Connections closed because the RESET before reusing them failed
+    pub reset_failures: u64,
+}
+
+/// Durations counted in buckets, like the `le` buckets of a Prometheus histogram
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Histogram {
+    /// The upper bounds of the buckets
+    pub bounds: Vec<Duration>,
+    /// The number of durations per bucket, the last count is for durations above all bounds
+    pub counts: Vec<u64>,
+    /// The sum of all durations
+    pub sum: Duration,
+}
+
+impl Histogram {
+    fn new() -> Histogram {
+        Histogram {
+            bounds: BUCKETS.to_vec(),
+            counts: vec![0; BUCKETS.len() + 1],
+            sum: Duration::ZERO,
+        }
+    }
+
+    fn record(&mut self, duration: Duration) {
+        let bucket = self
+            .bounds
+            .iter()
+            .position(|&bound| duration <= bound)
+            .unwrap_or(self.bounds.len());
+        self.counts[bucket] += 1;
+        self.sum += duration;
+    }
+
+    /// The number of durations recorded
+    pub fn count(&self) -> u64 {
+        self.counts.iter().sum()
+    }
+}
+
+/// The events of a pool counted by its connection manager. With the `metrics` feature they are
+/// also recorded with the `metrics` crate, labelled with the address of the server.
+#[derive(Debug)]
+pub(crate) struct PoolCounters {
+    address: String,
+    created: AtomicU64,
+    failed_to_create: AtomicU64,
+    acquired: AtomicU64,
+    acquisition_waits: AtomicU64,
+    reset_failures: AtomicU64,
+    acquisition_time: Mutex<Histogram>,
+}
+
+impl PoolCounters {
+    pub(crate) fn new(address: String) -> PoolCounters {
+        PoolCounters {
+            address,
+            created: AtomicU64::new(0),
+            failed_to_create: AtomicU64::new(0),
+            acquired: AtomicU64::new(0),
+            acquisition_waits: AtomicU64::new(0),
+            reset_failures: AtomicU64::new(0),
+            acquisition_time: Mutex::new(Histogram::new()),
+        }
+    }
+
+    pub(crate) fn connection_created(&self) {
+        self.created.fetch_add(1, Ordering::Relaxed);
+        #[cfg(feature = "metrics")]
+        exporter::increment("neo4rs_connections_created_total", &self.address);
+    }
+
+    pub(crate) fn creation_failed(&self) {
+        self.failed_to_create.fetch_add(1, Ordering::Relaxed);
+        #[cfg(feature = "metrics")]
+        exporter::increment("neo4rs_connections_failed_total", &self.address);
+    }
+
+    pub(crate) fn reset_failed(&self) {
+        self.reset_failures.fetch_add(1, Ordering::Relaxed);
+        #[cfg(feature = "metrics")]
+        exporter::increment("neo4rs_connection_reset_failures_total", &self.address);
+    }
+
+    /// A connection was handed out after `time`, `waited` if all connections were in use
+    pub(crate) fn connection_acquired(&self, time: Duration, waited: bool, status: Status) {
+        self.acquired.fetch_add(1, Ordering::Relaxed);
+        if waited {
+            self.acquisition_waits.fetch_add(1, Ordering::Relaxed);
+        }
+        self.acquisition_time.lock().unwrap().record(time);
+        #[cfg(feature = "metrics")]
+        exporter::acquired(&self.address, time, waited, status);
+        #[cfg(not(feature = "metrics"))]
+        let _ = status;
+    }
+
+    pub(crate) fn snapshot(&self, status: Status) -> PoolMetrics {
+        let created = self.created.load(Ordering::Relaxed);
+        PoolMetrics {
+            address: self.address.clone(),
+            created,
+            closed: created.saturating_sub(status.size as u64),
+            failed_to_create: self.failed_to_create.load(Ordering::Relaxed),
+            in_use: status.in_use(),
+            idle: status.idle(),
+            acquired: self.acquired.load(Ordering::Relaxed),
+            acquisition_waits: self.acquisition_waits.load(Ordering::Relaxed),
+            acquisition_time: self.acquisition_time.lock().unwrap().clone(),
+            reset_failures: self.reset_failures.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// The size of a pool, see [`deadpool::Status`]
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Status {
+    pub(crate) size: usize,
+    pub(crate) available: isize,
+}
+
+impl Status {
+    fn idle(&self) -> usize {
+        self.available.max(0) as usize
+    }
+
+    fn in_use(&self) -> usize {
+        self.size - self.idle()
+    }
+}
+
+impl From<deadpool::Status> for Status {
+    fn from(status: deadpool::Status) -> Self {
+        Status {
+            size: status.size,
+            available: status.available,
+        }
+    }
+}
+
+#[cfg(feature = "metrics")]
+mod exporter {
+    use super::Status;
+    use std::time::Duration;
+
+    pub(super) fn increment(name: &'static str, address: &str) {
+        ::metrics::increment_counter!(name, "address" => address.to_owned());
+    }
+
+    pub(super) fn acquired(address: &str, time: Duration, waited: bool, status: Status) {
+        let address = address.to_owned();
+        increment("neo4rs_connections_acquired_total", &address);
+        if waited {
+            increment("neo4rs_connection_acquisition_waits_total", &address);
+        }
+        ::metrics::histogram!(
+            "neo4rs_connection_acquisition_seconds",
+            time,
+            "address" => address.clone()
+        );
+        ::metrics::gauge!(
+            "neo4rs_connections_in_use",
+            status.in_use() as f64,
+            "address" => address.clone()
+        );
+        ::metrics::gauge!(
+            "neo4rs_connections_idle",
+            status.idle() as f64,
+            "address" => address
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_count_durations_in_buckets() {
+        let mut histogram = Histogram::new();
+
+        histogram.record(Duration::from_micros(200));
+        histogram.record(Duration::from_millis(1));
+        histogram.record(Duration::from_millis(70));
+        histogram.record(Duration::from_secs(10));
+
+        assert_eq!(histogram.counts, [2, 0, 0, 0, 1, 0, 0, 0, 1]);
+        assert_eq!(histogram.count(), 4);
+        assert_eq!(histogram.sum, Duration::from_micros(10_071_200));
+    }
+
+    #[test]
+    fn should_take_a_snapshot() {
+        let counters = PoolCounters::new("localhost:7687".into());
+        for _ in 0..3 {
+            counters.connection_created();
+        }
+        counters.creation_failed();
+        counters.reset_failed();
+        let status = Status {
+            size: 2,
+            available: 1,
+        };
+        counters.connection_acquired(Duration::from_millis(2), true, status);
+
+        let metrics = counters.snapshot(status);
+
+        assert_eq!(metrics.address, "localhost:7687");
+        assert_eq!(metrics.created, 3);
+        assert_eq!(metrics.closed, 1);
+        assert_eq!(metrics.failed_to_create, 1);
+        assert_eq!((metrics.in_use, metrics.idle), (1, 1));
+        assert_eq!((metrics.acquired, metrics.acquisition_waits), (1, 1));
+        assert_eq!(metrics.acquisition_time.counts[1], 1);
+        assert_eq!(metrics.reset_failures, 1);
+    }
+}
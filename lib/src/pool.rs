@@ -1,26 +1,73 @@
 use crate::config::Config;
-use crate::connection::Connection;
+use crate::connection::{Connection, ConnectionInfo};
 use crate::errors::Error;
+use crate::metrics::{PoolCounters, PoolMetrics};
+use crate::routing::{AccessMode, Router};
+use crate::trace::{namespace, record_server, span, traced, Empty};
 use async_trait::async_trait;
-use log::info;
+use deadpool::managed::RecycleError;
+use log::{info, warn};
+use std::time::{Duration, Instant};
 
 pub type ConnectionPool = deadpool::managed::Pool<ConnectionManager>;
 pub type ManagedConnection = deadpool::managed::Object<ConnectionManager>;
 
+/// The settings shared by the pools of all cluster members
+#[derive(Debug, Clone)]
+pub struct PoolConfig {
+    pub max_connections: usize,
+    pub max_connection_lifetime: Duration,
+    pub idle_time_before_connection_test: Option<Duration>,
+    pub max_connection_idle_time: Option<Duration>,
+    pub min_idle: usize,
+}
+
+impl PoolConfig {
+    pub fn new(config: &Config) -> PoolConfig {
+        PoolConfig {
+            max_connections: config.max_connections,
+            max_connection_lifetime: config.max_connection_lifetime,
+            idle_time_before_connection_test: config.idle_time_before_connection_test,
+            max_connection_idle_time: config.max_connection_idle_time,
+            min_idle: config.min_idle,
+        }
+    }
+
+    /// How often idle connections are checked, often enough to close them soon after they
+    /// expire. Without a max idle time expired connections are only closed when they are
+    /// acquired.
+    fn eviction_interval(&self) -> Option<Duration> {
+        self.max_connection_idle_time.map(|idle_time| {
+            (idle_time.min(self.max_connection_lifetime) / 2).max(Duration::from_millis(10))
+        })
+    }
+}
+
 pub struct ConnectionManager {
-    uri: String,
-    user: String,
-    password: String,
+    info: ConnectionInfo,
+    config: PoolConfig,
+    counters: PoolCounters,
 }
 
 impl ConnectionManager {
-    pub fn new(uri: &str, user: &str, password: &str) -> ConnectionManager {
+    pub fn new(info: ConnectionInfo, config: PoolConfig) -> ConnectionManager {
+        let counters = PoolCounters::new(info.address());
         ConnectionManager {
-            uri: uri.to_owned(),
-            user: user.to_owned(),
-            password: password.to_owned(),
+            info,
+            config,
+            counters,
         }
     }
+
+    /// Whether a connection is too old or was idle for too long to be used again
+    fn is_expired(&self, conn: &Connection) -> bool {
+        conn.age() >= self.config.max_connection_lifetime
+            || matches!(self.config.max_connection_idle_time, Some(t) if conn.idle_time() >= t)
+    }
+
+    fn needs_test(&self, conn: &Connection) -> bool {
+        matches!(self.config.idle_time_before_connection_test, Some(t) if conn.idle_time() >= t)
+    }
 }
 
 #[async_trait]
@@ -29,22 +76,340 @@ impl deadpool::managed::Manager for ConnectionManager {
     type Error = Error;
 
     async fn create(&self) -> std::result::Result<Connection, Error> {
-        info!("creating new connection...");
-        Connection::new(&self.uri, &self.user, &self.password).await
+        info!("creating new connection to {}...", self.info.address());
+        let result = match Connection::new(&self.info).await {
+            // the manager was told about the expired token, so try once more with a new one
+            Err(Error::Neo4jError(e)) if e.is_token_expired() => Connection::new(&self.info).await,
+            result => result,
+        };
+        match &result {
+            Ok(_) => self.counters.connection_created(),
+            Err(_) => self.counters.creation_failed(),
+        }
+        result
     }
 
     async fn recycle(&self, conn: &mut Connection) -> deadpool::managed::RecycleResult<Error> {
-        Ok(conn.reset().await?)
+        if conn.is_defunct() {
+            return Err(RecycleError::StaticMessage("connection is defunct"));
+        }
+        if self.is_expired(conn) {
+            return Err(RecycleError::StaticMessage("connection expired"));
+        }
+        if conn.is_token_expired() {
+            self.info.auth.on_token_expired(conn.auth_token()).await;
+        }
+        // a RESET is the round trip testing connections which were idle for long
+        if !conn.is_clean() || conn.is_token_expired() || self.needs_test(conn) {
+            if let Err(e) = conn.reset().await {
+                self.counters.reset_failed();
+                return Err(e.into());
+            }
+        }
+        let token = self.info.auth.get_token().await?;
+        if conn.is_token_expired() || &token != conn.auth_token() {
+            if !conn.version().supports_logon() {
+                return Err(RecycleError::StaticMessage(
+                    "auth token of connection changed",
+                ));
+            }
+            conn.reauthenticate(token).await?;
+        }
+        Ok(())
+    }
+}
+
+/// Where connections come from: a single pool for `bolt` uris, or a pool per cluster member
+/// for `neo4j` uris
+pub enum ConnectionProvider {
+    Direct(ConnectionPool),
+    Routed(Box<Router>),
+}
+
+impl ConnectionProvider {
+    /// A connection to a server able to serve the database of `config` in the given mode,
+    /// waiting at most [`crate::ConfigBuilder::connection_acquisition_timeout`] for it. The
+    /// `bookmarks` of the work it is acquired for are sent along when a routing table is fetched.
+    pub async fn get(
+        &self,
+        mode: AccessMode,
+        config: &Config,
+        bookmarks: &[String],
+    ) -> Result<ManagedConnection, Error> {
+        let connection = async {
+            match self {
+                ConnectionProvider::Direct(pool) => acquire(pool).await,
+                ConnectionProvider::Routed(router) => {
+                    router
+                        .acquire(mode, &config.db, config.imp_user.as_deref(), bookmarks)
+                        .await
+                }
+            }
+        };
+        let connection = async {
+            tokio::time::timeout(config.connection_acquisition_timeout, connection)
+                .await
+                .map_err(|_| Error::ConnectionAcquisitionTimeout)?
+        };
+        let span = span!(
+            "acquire connection",
+            db.namespace = namespace(&config.db),
+            neo4j.access_mode = mode.as_str(),
+            server.address = Empty,
+            server.port = Empty,
+        );
+        let connection = traced(span.clone(), connection).await?;
+        record_server(&span, &connection);
+        Ok(connection)
+    }
+
+    /// Lets the router forget `address` if `result` failed because the server is gone or no
+    /// longer accepts writes
+    pub async fn check<T>(
+        &self,
+        address: Option<String>,
+        result: Result<T, Error>,
+    ) -> Result<T, Error> {
+        if let (ConnectionProvider::Routed(router), Some(address), Err(e)) =
+            (self, address, &result)
+        {
+            router.on_error(&address, e).await;
+        }
+        result
+    }
+}
+
+impl ConnectionProvider {
+    /// The metrics of the pools of all servers, ordered by address
+    pub fn metrics(&self) -> Vec<PoolMetrics> {
+        let mut metrics = match self {
+            ConnectionProvider::Direct(pool) => vec![metrics(pool)],
+            ConnectionProvider::Routed(router) => router.pools().iter().map(metrics).collect(),
+        };
+        metrics.sort_by(|a, b| a.address.cmp(&b.address));
+        metrics
     }
 }
 
-pub async fn create_pool(config: &Config) -> Result<ConnectionPool, Error> {
-    let mgr = ConnectionManager::new(&config.uri, &config.user, &config.password);
+/// Closes the pool, which also stops its eviction task, connections still in use are closed
+/// when they are returned
+impl Drop for ConnectionProvider {
+    fn drop(&mut self) {
+        if let ConnectionProvider::Direct(pool) = self {
+            pool.close();
+        }
+    }
+}
+
+/// Takes a connection from the pool, counting how long it took
+pub async fn acquire(pool: &ConnectionPool) -> Result<ManagedConnection, Error> {
+    let status = pool.status();
+    let waited = status.available <= 0 && status.size >= status.max_size;
+    let start = Instant::now();
+    let connection = pool.get().await?;
+    pool.manager()
+        .counters
+        .connection_acquired(start.elapsed(), waited, pool.status().into());
+    Ok(connection)
+}
+
+pub fn metrics(pool: &ConnectionPool) -> PoolMetrics {
+    pool.manager().counters.snapshot(pool.status().into())
+}
+
+/// The `host:port` of the server a pooled connection is connected to
+pub fn server_address(connection: &ManagedConnection) -> Option<String> {
+    ManagedConnection::pool(connection).map(|pool| pool.manager().info.address())
+}
+
+pub fn build_pool(info: ConnectionInfo, config: &PoolConfig) -> Result<ConnectionPool, Error> {
     info!(
-        "creating connection pool with max size {}",
+        "creating connection pool for {} with max size {}",
+        info.address(),
         config.max_connections
     );
-    Ok(ConnectionPool::builder(mgr)
+    let pool = ConnectionPool::builder(ConnectionManager::new(info, config.clone()))
         .max_size(config.max_connections)
-        .build()?)
+        .build()?;
+    if let Some(interval) = config.eviction_interval() {
+        evict_expired_connections(pool.clone(), interval);
+    }
+    Ok(pool)
+}
+
+/// Opens connections until the pool has `min_idle` of them, idle connections count and are
+/// tested like any acquired connection
+pub async fn warm_up(pool: &ConnectionPool, min_idle: usize) -> Result<(), Error> {
+    let status = pool.status();
+    if status.size >= min_idle {
+        return Ok(());
+    }
+    let in_use = status.size - status.available.max(0) as usize;
+    let mut connections = Vec::with_capacity(min_idle);
+    for _ in in_use..min_idle {
+        connections.push(pool.get().await?);
+    }
+    Ok(())
+}
+
+/// Closes idle connections which expired in the background and opens new ones in their place,
+/// until the pool is closed
+fn evict_expired_connections(pool: ConnectionPool, interval: Duration) {
+    if tokio::runtime::Handle::try_current().is_err() {
+        return;
+    }
+    tokio::spawn(async move {
+        let mut ticks = tokio::time::interval(interval);
+        loop {
+            ticks.tick().await;
+            if pool.is_closed() {
+                break;
+            }
+            let manager = pool.manager();
+            pool.retain(|conn, _| !manager.is_expired(conn));
+            if let Err(e) = warm_up(&pool, manager.config.min_idle).await {
+                warn!("could not open idle connections: {}", e);
+            }
+        }
+    });
+}
+
+/// Creates the pool and opens the first connections, so that unreachable servers and invalid
+/// credentials are reported right away. For clusters the routing table of the configured
+/// database is fetched, a reader and a writer have to be reachable and the pool of each of them
+/// is filled up to `min_idle` connections.
+pub async fn create_pool(config: &Config) -> Result<ConnectionProvider, Error> {
+    let mut info = ConnectionInfo::new(&config.uri, config.auth.clone(), &config.tls)?;
+    info.connect_timeout = config.connect_timeout;
+    let provider = if info.uri.routing {
+        info!("routing queries within the cluster of {}", info.address());
+        ConnectionProvider::Routed(Box::new(Router::new(info, PoolConfig::new(config))))
+    } else {
+        ConnectionProvider::Direct(build_pool(info, &PoolConfig::new(config))?)
+    };
+    match &provider {
+        ConnectionProvider::Direct(pool) => warm_up(pool, config.min_idle).await?,
+        ConnectionProvider::Routed(router) if config.min_idle > 0 => {
+            for mode in [AccessMode::Read, AccessMode::Write] {
+                drop(provider.get(mode, config, &[]).await?);
+            }
+            router
+                .warm_up(&config.db, config.imp_user.as_deref(), config.min_idle)
+                .await;
+        }
+        ConnectionProvider::Routed(_) => {}
+    }
+    Ok(provider)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::auth::AuthToken;
+    use crate::connection::tests::*;
+    use crate::messages::BoltRequest;
+    use crate::transport::TlsConfig;
+    use deadpool::managed::Manager;
+    use std::sync::Arc;
+
+    pub(crate) fn pool_config(max_connections: usize) -> PoolConfig {
+        PoolConfig {
+            max_connections,
+            max_connection_lifetime: Duration::from_secs(3600),
+            idle_time_before_connection_test: None,
+            max_connection_idle_time: None,
+            min_idle: 0,
+        }
+    }
+
+    #[tokio::test]
+    async fn should_reauthenticate_connections_with_an_expired_token() {
+        let (uri, requests) = versioned_stub_server(
+            [0, 0, 1, 5],
+            vec![
+                vec![success()],
+                vec![success()],
+                vec![failure("Neo.ClientError.Security.TokenExpired")],
+                vec![success()],
+                vec![],
+                vec![success(), success()],
+            ],
+        )
+        .await;
+        let manager = TestAuthManager::new(AuthToken::bearer("old"));
+        let info = ConnectionInfo::new(&uri, manager.clone(), &TlsConfig::default()).unwrap();
+        let pool = build_pool(info, &pool_config(1)).unwrap();
+
+        let mut connection = pool.get().await.unwrap();
+        let q = BoltRequest::run("", "RETURN 1", Default::default(), Default::default());
+        connection.send_recv(q).await.unwrap();
+        drop(connection);
+        manager.set(AuthToken::bearer("new"));
+
+        let connection = pool.get().await.unwrap();
+
+        assert_eq!(connection.auth_token(), &AuthToken::bearer("new"));
+        assert_eq!(
+            *manager.expired.lock().unwrap(),
+            vec![AuthToken::bearer("old")]
+        );
+        assert_eq!(requests.lock().unwrap().len(), 6);
+    }
+
+    fn info(uri: &str) -> ConnectionInfo {
+        let auth = Arc::new(AuthToken::basic("user", "pass"));
+        ConnectionInfo::new(uri, auth, &TlsConfig::default()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn should_only_reset_connections_which_need_it() {
+        let (uri, requests) = recording_stub_server(vec![vec![success()], vec![success()]]).await;
+        let config = PoolConfig {
+            idle_time_before_connection_test: Some(Duration::from_millis(50)),
+            ..pool_config(1)
+        };
+        let pool = build_pool(info(&uri), &config).unwrap();
+
+        drop(pool.get().await.unwrap());
+        drop(pool.get().await.unwrap());
+        assert_eq!(requests.lock().unwrap().len(), 1);
+
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        drop(pool.get().await.unwrap());
+        let requests = requests.lock().unwrap();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[1], request_bytes(BoltRequest::reset()));
+    }
+
+    #[tokio::test]
+    async fn should_not_reuse_expired_connections() {
+        let uri = stub_server(vec![vec![success()]]).await;
+        let config = PoolConfig {
+            max_connection_lifetime: Duration::from_millis(50),
+            ..pool_config(1)
+        };
+        let pool = build_pool(info(&uri), &config).unwrap();
+        let mut connection = pool.get().await.unwrap();
+        assert!(pool.manager().recycle(&mut connection).await.is_ok());
+
+        tokio::time::sleep(Duration::from_millis(60)).await;
+
+        assert!(pool.manager().recycle(&mut connection).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn should_evict_idle_connections() {
+        let uri = stub_server(vec![vec![success()]]).await;
+        let config = PoolConfig {
+            max_connection_idle_time: Some(Duration::from_millis(50)),
+            ..pool_config(1)
+        };
+        let pool = build_pool(info(&uri), &config).unwrap();
+        drop(pool.get().await.unwrap());
+        assert_eq!(pool.status().size, 1);
+
+        tokio::time::sleep(Duration::from_millis(200)).await;
+
+        assert_eq!(pool.status().size, 0);
+    }
 }
@@ -3,7 +3,11 @@ use crate::errors::*;
 use crate::messages::*;
 use crate::pool::*;
 use crate::stream::*;
+use crate::summary::ResultSummary;
+use crate::trace::*;
 use crate::types::*;
+use std::collections::HashMap;
+use std::hash::Hash;
 use std::sync::Arc;
 use tokio::sync::Mutex;
 
@@ -12,6 +16,34 @@ use tokio::sync::Mutex;
 pub struct Query {
     query: String,
     params: BoltMap,
+    extra: BoltMap,
+    bookmarks: Vec<String>,
+    imp_user: Option<String>,
+    error: Option<Error>,
+}
+
+/// Values accepted by [`Query::param`]: anything convertible into a bolt value, and references
+/// to vectors or maps of types implementing [`serde::Serialize`]
+pub trait IntoParam {
+    fn into_param(self) -> Result<BoltType>;
+}
+
+impl<T: Into<BoltType>> IntoParam for T {
+    fn into_param(self) -> Result<BoltType> {
+        Ok(self.into())
+    }
+}
+
+impl<T: serde::Serialize> IntoParam for &Vec<T> {
+    fn into_param(self) -> Result<BoltType> {
+        BoltType::from_serialize(self)
+    }
+}
+
+impl<K: serde::Serialize + Eq + Hash, V: serde::Serialize> IntoParam for &HashMap<K, V> {
+    fn into_param(self) -> Result<BoltType> {
+        BoltType::from_serialize(self)
+    }
 }
 
 impl Query {
@@ -19,48 +51,251 @@ impl Query {
         Query {
             query,
             params: BoltMap::default(),
+            extra: BoltMap::default(),
+            bookmarks: Vec::new(),
+            imp_user: None,
+            error: None,
         }
     }
 
-    pub fn param<T: std::convert::Into<BoltType>>(mut self, key: &str, value: T) -> Self {
-        self.params.put(key.into(), value.into());
+    /// Add a parameter, values which fail to serialize make the query fail when it is run
+    pub fn param<T: IntoParam>(mut self, key: &str, value: T) -> Self {
+        match value.into_param() {
+            Ok(value) => self.params.put(key.into(), value),
+            Err(e) => self.fail(e),
+        }
         self
     }
 
-    pub(crate) async fn run(
-        self,
+    /// Add every field of a struct, or entry of a map, as a parameter
+    pub fn params<T: serde::Serialize + ?Sized>(mut self, params: &T) -> Self {
+        match BoltType::from_serialize(params) {
+            Ok(BoltType::Map(map)) => self.params.value.extend(map.value),
+            Ok(_) => self.fail(Error::SerializationError(
+                "query parameters must serialize to a map".into(),
+            )),
+            Err(e) => self.fail(e),
+        }
+        self
+    }
+
+    /// Bookmarks the server has to catch up with before an auto-commit query runs, queries of a
+    /// transaction wait for the bookmarks of the transaction instead
+    pub fn bookmarks(mut self, bookmarks: Vec<String>) -> Self {
+        self.add_bookmarks(bookmarks);
+        self
+    }
+
+    /// The user an auto-commit query is executed as, instead of the user of the session or
+    /// [`crate::TxnConfig::impersonate`]. Queries of a transaction run as the user of the
+    /// transaction instead. Requires Neo4j 4.4 or later.
+    pub fn impersonate(mut self, user: &str) -> Self {
+        self.imp_user = Some(user.to_owned());
+        self
+    }
+
+    pub(crate) fn take_imp_user(&mut self) -> Option<String> {
+        self.imp_user.take()
+    }
+
+    pub(crate) fn add_bookmarks(&mut self, bookmarks: Vec<String>) {
+        for bookmark in bookmarks {
+            if !self.bookmarks.contains(&bookmark) {
+                self.bookmarks.push(bookmark);
+            }
+        }
+    }
+
+    pub(crate) fn sent_bookmarks(&self) -> &[String] {
+        &self.bookmarks
+    }
+
+    /// Adds the options of the auto-commit transaction the query runs in
+    pub(crate) fn extend_extra(mut self, extra: BoltMap) -> Self {
+        self.extra.value.extend(extra.value);
+        self
+    }
+
+    fn into_request(self, config: &Config) -> BoltRequest {
+        let mut extra = self.extra;
+        if !self.bookmarks.is_empty() {
+            extra.put("bookmarks".into(), self.bookmarks.into());
+        }
+        BoltRequest::run(&config.db, &self.query, self.params, extra)
+    }
+
+    /// The span of the RUN of the query
+    fn span(&self, config: &Config) -> Span {
+        span!(
+            "RUN",
+            db.operation.name = "RUN",
+            db.namespace = namespace(&config.db),
+            db.query.text = self.query.as_str(),
+            neo4j.qid = Empty,
+            server.address = Empty,
+            server.port = Empty,
+        )
+    }
+
+    fn fail(&mut self, error: Error) {
+        if self.error.is_none() {
+            self.error = Some(error);
+        }
+    }
+
+    /// Sends the RUN and DISCARD of all queries together and then waits for the responses. The
+    /// summaries of the queries before the first failed one are returned with the failure, the
+    /// server ignores the queries after it.
+    pub(crate) async fn run_batch(
+        queries: Vec<Query>,
         config: &Config,
         connection: Arc<Mutex<ManagedConnection>>,
-    ) -> Result<()> {
-        let run = BoltRequest::run(&config.db, &self.query, self.params.clone());
+    ) -> (Vec<ResultSummary>, Option<Error>) {
+        let failed = |index, error| Error::BatchFailed {
+            index,
+            error: Box::new(error),
+        };
+        let span = span!(
+            "RUN",
+            db.operation.name = "RUN",
+            db.operation.batch.size = queries.len(),
+            db.namespace = namespace(&config.db),
+            server.address = Empty,
+            server.port = Empty,
+        );
+        let mut requests = Vec::with_capacity(queries.len());
+        for (index, mut query) in queries.into_iter().enumerate() {
+            if let Some(e) = query.error.take() {
+                return (Vec::new(), Some(failed(index, e)));
+            }
+            requests.push(query.into_request(config));
+        }
+
         let mut connection = connection.lock().await;
-        match connection.send_recv(run).await? {
-            BoltResponse::Success(_) => match connection.send_recv(BoltRequest::discard()).await? {
-                BoltResponse::Success(_) => Ok(()),
-                msg => Err(unexpected(msg, "DISCARD")),
-            },
-            msg => Err(unexpected(msg, "RUN")),
+        record_server(&span, &connection);
+        let mut sent = 0;
+        let mut send_failure = None;
+        for run in requests {
+            let result = match connection.send(run).await {
+                Ok(()) => connection.send(BoltRequest::discard(-1)).await,
+                Err(e) => Err(e),
+            };
+            if let Err(e) = result {
+                send_failure = Some(failed(sent, e));
+                break;
+            }
+            sent += 1;
+        }
+        if connection.is_defunct() {
+            if let Some(e) = &send_failure {
+                record_error(&span, e);
+            }
+            return (Vec::new(), send_failure);
         }
+
+        let mut summaries = Vec::with_capacity(sent);
+        let mut failure = None;
+        for index in 0..sent {
+            let run = connection.recv().await;
+            let discard = connection.recv().await;
+            match summary(run, discard) {
+                Ok(summary) if failure.is_none() => summaries.push(summary),
+                Err(e) if failure.is_none() => failure = Some(failed(index, e)),
+                _ => {}
+            }
+            if connection.is_defunct() {
+                break;
+            }
+        }
+        let failure = failure.or(send_failure);
+        if let Some(e) = &failure {
+            record_error(&span, e);
+        }
+        (summaries, failure)
+    }
+
+    /// Sends RUN and DISCARD together and waits for both responses
+    pub(crate) async fn run(
+        mut self,
+        config: &Config,
+        connection: Arc<Mutex<ManagedConnection>>,
+    ) -> Result<ResultSummary> {
+        if let Some(e) = self.error.take() {
+            return Err(e);
+        }
+        let span = self.span(config);
+        let run = self.into_request(config);
+        let result = async {
+            let mut connection = connection.lock().await;
+            record_server(&span, &connection);
+            connection.send(run).await?;
+            connection.send(BoltRequest::discard(-1)).await?;
+            let run = connection.recv().await;
+            let discard = connection.recv().await;
+            summary(run, discard)
+        };
+        traced(span.clone(), result).await
     }
 
+    /// Sends RUN together with the PULL of the first batch, whose rows the [`RowStream`]
+    /// receives
     pub(crate) async fn execute(
-        self,
+        mut self,
         config: &Config,
         connection: Arc<Mutex<ManagedConnection>>,
     ) -> Result<RowStream> {
-        let run = BoltRequest::run(&config.db, &self.query, self.params);
-        match connection.lock().await.send_recv(run).await {
-            Ok(BoltResponse::Success(success)) => {
-                let fields: BoltList = success.get("fields").unwrap_or_else(BoltList::new);
-                let qid: i64 = success.get("qid").unwrap_or(-1);
-                Ok(RowStream::new(
-                    qid,
-                    fields,
-                    config.fetch_size,
-                    connection.clone(),
-                ))
-            }
-            msg => Err(unexpected(msg, "RUN")),
+        if let Some(e) = self.error.take() {
+            return Err(e);
         }
+        let span = self.span(config);
+        let db = config.db.clone();
+        let run = self.into_request(config);
+        let result = async {
+            let mut guard = connection.lock().await;
+            record_server(&span, &guard);
+            guard.send(run).await?;
+            let pull = BoltRequest::pull(config.fetch_size, -1);
+            guard.send_for(-1, pull).await?;
+            match guard.recv().await {
+                Ok(BoltResponse::Success(success)) => {
+                    let fields: BoltList = success.get("fields").unwrap_or_else(BoltList::new);
+                    let qid: i64 = success.get("qid").unwrap_or(-1);
+                    guard.assign_qid(qid);
+                    span.record("neo4j.qid", qid);
+                    let mut stream = RowStream::new(
+                        qid,
+                        fields,
+                        success.get("t_first"),
+                        config.fetch_size,
+                        db,
+                        connection.clone(),
+                    );
+                    stream.start_batch(&guard);
+                    Ok(stream)
+                }
+                result => {
+                    // the PULL is ignored by the server once RUN failed
+                    let _ = guard.recv_for(-1).await;
+                    match result? {
+                        BoltResponse::Failure(failure) => Err(failure.into_error()),
+                        msg => Err(unexpected(msg, "RUN")),
+                    }
+                }
+            }
+        };
+        traced(span.clone(), result).await
+    }
+}
+
+/// The summary of a query from the responses to its RUN and DISCARD
+fn summary(run: Result<BoltResponse>, discard: Result<BoltResponse>) -> Result<ResultSummary> {
+    match run? {
+        BoltResponse::Success(run) => match discard? {
+            BoltResponse::Success(discard) => Ok(ResultSummary::new(&discard, run.get("t_first"))),
+            BoltResponse::Failure(failure) => Err(failure.into_error()),
+            msg => Err(unexpected(msg, "DISCARD")),
+        },
+        BoltResponse::Failure(failure) => Err(failure.into_error()),
+        msg => Err(unexpected(msg, "RUN")),
     }
 }
@@ -0,0 +1,152 @@
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::time::Duration;
+use tokio::time::Instant;
+
+const INITIAL_DELAY: Duration = Duration::from_secs(1);
+const MULTIPLIER: f64 = 2.0;
+const JITTER: f64 = 0.2;
+
+/// Exponential backoff between the attempts of a transaction function: the delay starts at one
+/// second, doubles after every attempt and is randomly spread by 20% so that clients failing
+/// together don't retry together. Gives up once `max_retry_time` passed since the first attempt.
+pub struct Backoff {
+    start: Instant,
+    max_retry_time: Duration,
+    delay: Duration,
+}
+
+impl Backoff {
+    pub fn new(max_retry_time: Duration) -> Backoff {
+        Backoff {
+            start: Instant::now(),
+            max_retry_time,
+            delay: INITIAL_DELAY,
+        }
+    }
+
+    /// How long to wait before the next attempt, `None` when the retry time is used up
+    pub fn next_delay(&mut self) -> Option<Duration> {
+        if self.start.elapsed() >= self.max_retry_time {
+            return None;
+        }
+        let delay = jittered(self.delay, random());
+        self.delay = self.delay.mul_f64(MULTIPLIER);
+        Some(delay)
+    }
+}
+
+fn jittered(delay: Duration, random: f64) -> Duration {
+    delay.mul_f64(1.0 - JITTER + 2.0 * JITTER * random)
+}
+
+/// A number in `[0, 1)`, every `RandomState` is seeded differently which is random enough to
+/// spread retries
+fn random() -> f64 {
+    let bits = RandomState::new().build_hasher().finish() >> 11;
+    bits as f64 / (1u64 << 53) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::connection::tests::{failure, ignored, stub_server, success};
+    use crate::errors::*;
+    use crate::{config, query, Graph};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn should_spread_delays() {
+        assert_eq!(jittered(INITIAL_DELAY, 0.0), Duration::from_millis(800));
+        assert_eq!(jittered(INITIAL_DELAY, 0.5), Duration::from_secs(1));
+        assert!(jittered(INITIAL_DELAY, 0.999) < Duration::from_millis(1200));
+        for _ in 0..100 {
+            assert!((0.0..1.0).contains(&random()));
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn should_back_off_exponentially_until_the_retry_time_is_used_up() {
+        let mut backoff = Backoff::new(Duration::from_secs(10));
+
+        let mut expected = INITIAL_DELAY;
+        while let Some(delay) = backoff.next_delay() {
+            assert!(delay >= expected.mul_f64(0.8) && delay <= expected.mul_f64(1.2));
+            expected *= 2;
+            tokio::time::sleep(delay).await;
+        }
+        assert!(backoff.start.elapsed() >= Duration::from_secs(10));
+        assert!(expected <= Duration::from_secs(16));
+    }
+
+    /// Connects to the stub server, then pauses the clock so that backoffs pass instantly. A
+    /// paused clock would run out the connect timeout while waiting for the server.
+    async fn connect(uri: &str) -> Graph {
+        let config = config()
+            .uri(uri)
+            .user("user")
+            .password("pass")
+            .max_connections(1)
+            .build()
+            .unwrap();
+        let graph = Graph::connect(config).await.unwrap();
+        tokio::time::pause();
+        graph
+    }
+
+    #[tokio::test]
+    async fn should_retry_transient_failures() {
+        let uri = stub_server(vec![
+            vec![success()],
+            vec![success()],
+            vec![failure("Neo.TransientError.Transaction.DeadlockDetected")],
+            vec![ignored()],
+            vec![success()],
+            vec![success()],
+            vec![success()],
+            vec![success()],
+            vec![success()],
+        ])
+        .await;
+        let graph = connect(&uri).await;
+        let attempts = AtomicUsize::new(0);
+
+        let value = graph
+            .execute_write(|txn| {
+                attempts.fetch_add(1, Ordering::SeqCst);
+                async move {
+                    txn.run(query("CREATE (n:Counter)")).await?;
+                    Ok(42)
+                }
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(value, 42);
+        assert_eq!(attempts.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn should_not_retry_other_failures() {
+        let uri = stub_server(vec![
+            vec![success()],
+            vec![success()],
+            vec![failure("Neo.ClientError.Statement.SyntaxError")],
+            vec![ignored()],
+            vec![success()],
+        ])
+        .await;
+        let graph = connect(&uri).await;
+        let attempts = AtomicUsize::new(0);
+
+        let result: Result<()> = graph
+            .execute_read(|txn| {
+                attempts.fetch_add(1, Ordering::SeqCst);
+                async move { txn.run(query("MATCH (n) RETRUN n")).await }
+            })
+            .await;
+
+        assert!(matches!(result, Err(Error::Neo4jError(_))));
+        assert_eq!(attempts.load(Ordering::SeqCst), 1);
+    }
+}
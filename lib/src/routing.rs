@@ -0,0 +1,784 @@
+use crate::connection::ConnectionInfo;
+use crate::errors::*;
+use crate::messages::*;
+use crate::pool::*;
+use crate::types::*;
+use log::{info, warn};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+
+/// Whether a query only reads, and may be served by any member of a cluster, or also writes and
+/// has to be sent to the leader. Servers reached through a `bolt` uri accept both.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AccessMode {
+    Read,
+    #[default]
+    Write,
+}
+
+impl AccessMode {
+    pub(crate) fn as_str(self) -> &'static str {
+        match self {
+            AccessMode::Read => "read",
+            AccessMode::Write => "write",
+        }
+    }
+
+    /// Adds the `mode` entry of RUN and BEGIN, the server assumes writes when it is missing
+    pub(crate) fn put_into(self, extra: &mut BoltMap) {
+        if self == AccessMode::Read {
+            extra.put("mode".into(), "r".into());
+        }
+    }
+}
+
+/// How long a routing table is kept when its ttl can't be represented as an [`Instant`]
+const MAX_TTL: Duration = Duration::from_secs(365 * 24 * 60 * 60);
+
+/// The members of a cluster serving a database, as returned by the ROUTE message
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RoutingTable {
+    routers: Vec<String>,
+    readers: Vec<String>,
+    writers: Vec<String>,
+    expires: Option<Instant>,
+}
+
+impl RoutingTable {
+    /// Parses the `rt` entry of the ROUTE response: a `ttl` in seconds and `servers`, a list of
+    /// `{addresses, role}` maps where role is one of `ROUTE`, `READ` or `WRITE`
+    pub fn parse(rt: BoltMap, now: Instant) -> Result<RoutingTable> {
+        let invalid = || Error::UnexpectedMessage(format!("invalid routing table {:?}", rt));
+        let ttl: i64 = rt.get("ttl").ok_or_else(invalid)?;
+        let servers: Vec<BoltMap> = rt.get("servers").ok_or_else(invalid)?;
+        let expires = now
+            .checked_add(Duration::from_secs(ttl.max(0) as u64))
+            .unwrap_or_else(|| now + MAX_TTL);
+        let mut table = RoutingTable {
+            expires: Some(expires),
+            ..RoutingTable::default()
+        };
+        for server in servers {
+            let role: String = server.get("role").ok_or_else(invalid)?;
+            let addresses: Vec<String> = server.get("addresses").ok_or_else(invalid)?;
+            match role.as_str() {
+                "ROUTE" => table.routers.extend(addresses),
+                "READ" => table.readers.extend(addresses),
+                "WRITE" => table.writers.extend(addresses),
+                _ => {}
+            }
+        }
+        Ok(table)
+    }
+
+    pub fn servers(&self, mode: AccessMode) -> &[String] {
+        match mode {
+            AccessMode::Read => &self.readers,
+            AccessMode::Write => &self.writers,
+        }
+    }
+
+    /// Whether the table can be used for `mode`, tables are refreshed once their ttl expired or
+    /// when no server is left for the requested mode
+    pub fn is_fresh(&self, mode: AccessMode, now: Instant) -> bool {
+        match self.expires {
+            Some(expires) => {
+                expires > now && !self.routers.is_empty() && !self.servers(mode).is_empty()
+            }
+            None => false,
+        }
+    }
+
+    /// Removes a member which couldn't be reached
+    pub fn forget(&mut self, address: &str) {
+        self.routers.retain(|a| a != address);
+        self.readers.retain(|a| a != address);
+        self.writers.retain(|a| a != address);
+    }
+
+    /// Removes a member which is no longer the leader
+    pub fn forget_writer(&mut self, address: &str) {
+        self.writers.retain(|a| a != address);
+    }
+}
+
+/// Routes connections for `neo4j` uris: keeps a routing table per database and a connection pool
+/// per cluster member, readers are picked round robin and unreachable members are forgotten
+/// until the next refresh.
+pub struct Router {
+    info: ConnectionInfo,
+    pool_config: PoolConfig,
+    pools: std::sync::Mutex<HashMap<String, ConnectionPool>>,
+    /// Routing tables by database and impersonated user, whose home database may differ
+    tables: Mutex<HashMap<TableKey, RoutingTable>>,
+    /// Held while a routing table is fetched, so that a single ROUTE is sent per table
+    refreshes: std::sync::Mutex<HashMap<TableKey, Arc<Mutex<()>>>>,
+    next: AtomicUsize,
+}
+
+type TableKey = (String, Option<String>);
+
+/// Closes the pools of all members, see [`crate::pool::ConnectionProvider`]
+impl Drop for Router {
+    fn drop(&mut self) {
+        for pool in self.pools.lock().unwrap().values() {
+            pool.close();
+        }
+    }
+}
+
+impl Router {
+    pub fn new(info: ConnectionInfo, pool_config: PoolConfig) -> Router {
+        Router {
+            info,
+            pool_config,
+            pools: std::sync::Mutex::new(HashMap::new()),
+            tables: Mutex::new(HashMap::new()),
+            refreshes: std::sync::Mutex::new(HashMap::new()),
+            next: AtomicUsize::new(0),
+        }
+    }
+
+    /// Returns a connection to a member serving `db` in the given mode, falling back to the
+    /// other members and finally to a refreshed routing table when members can't be reached.
+    /// Other errors, like rejected credentials, are returned right away.
+    pub async fn acquire(
+        &self,
+        mode: AccessMode,
+        db: &str,
+        imp_user: Option<&str>,
+        bookmarks: &[String],
+    ) -> Result<ManagedConnection> {
+        for _ in 0..2 {
+            let servers = self.servers(mode, db, imp_user, bookmarks).await?;
+            let start = self.next.fetch_add(1, Ordering::Relaxed);
+            for i in 0..servers.len() {
+                let address = &servers[(start + i) % servers.len()];
+                match acquire(&self.pool(address)?).await {
+                    Ok(connection) => return Ok(connection),
+                    Err(e) if is_unreachable(&e) => {
+                        warn!("could not connect to {}: {}", address, e);
+                        self.forget(address).await;
+                    }
+                    Err(e) => return Err(e),
+                }
+            }
+        }
+        Err(Error::ServiceUnavailable(format!(
+            "no {:?} server of database {:?} could be reached",
+            mode, db
+        )))
+    }
+
+    /// Fills the pool of every reader and writer of `db` up to `min_idle` connections, members
+    /// which can't be reached are left to be forgotten when they are acquired
+    pub async fn warm_up(&self, db: &str, imp_user: Option<&str>, min_idle: usize) {
+        let table = self
+            .table(&(db.to_owned(), imp_user.map(str::to_owned)))
+            .await;
+        let mut members = table.readers;
+        for writer in table.writers {
+            if !members.contains(&writer) {
+                members.push(writer);
+            }
+        }
+        for address in members {
+            let result = match self.pool(&address) {
+                Ok(pool) => warm_up(&pool, min_idle).await,
+                Err(e) => Err(e),
+            };
+            if let Err(e) = result {
+                warn!("could not open idle connections to {}: {}", address, e);
+            }
+        }
+    }
+
+    /// Forgets the member a query failed on if the failure means it is gone or no longer leads
+    pub async fn on_error(&self, address: &str, error: &Error) {
+        let error = match error {
+            Error::BatchFailed { error, .. } => error,
+            error => error,
+        };
+        match error {
+            error if is_unreachable(error) => self.forget(address).await,
+            Error::Neo4jError(e) if e.is_not_writable() => {
+                info!("{} is no longer a writer", address);
+                for table in self.tables.lock().await.values_mut() {
+                    table.forget_writer(address);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    async fn servers(
+        &self,
+        mode: AccessMode,
+        db: &str,
+        imp_user: Option<&str>,
+        bookmarks: &[String],
+    ) -> Result<Vec<String>> {
+        let key = (db.to_owned(), imp_user.map(str::to_owned));
+        let mut table = self.table(&key).await;
+        if !table.is_fresh(mode, Instant::now()) {
+            let refresh = self
+                .refreshes
+                .lock()
+                .unwrap()
+                .entry(key.clone())
+                .or_default()
+                .clone();
+            let _refreshing = refresh.lock().await;
+            // another task may have refreshed the table while this one waited
+            table = self.table(&key).await;
+            if !table.is_fresh(mode, Instant::now()) {
+                table = self.fetch_table(&table, db, imp_user, bookmarks).await?;
+                self.tables.lock().await.insert(key, table.clone());
+            }
+        }
+        let servers = table.servers(mode).to_vec();
+        if servers.is_empty() {
+            return Err(Error::ServiceUnavailable(format!(
+                "the routing table of database {:?} has no {:?} servers",
+                db, mode
+            )));
+        }
+        Ok(servers)
+    }
+
+    /// A copy of the routing table of `key`, the lock isn't held while the table is refreshed
+    async fn table(&self, key: &TableKey) -> RoutingTable {
+        let tables = self.tables.lock().await;
+        tables.get(key).cloned().unwrap_or_default()
+    }
+
+    /// Asks the known routers, then the address of the uri, for a new routing table
+    async fn fetch_table(
+        &self,
+        table: &RoutingTable,
+        db: &str,
+        imp_user: Option<&str>,
+        bookmarks: &[String],
+    ) -> Result<RoutingTable> {
+        let mut routers = table.routers.clone();
+        let initial = self.info.address();
+        if !routers.contains(&initial) {
+            routers.push(initial);
+        }
+        for router in routers {
+            match self.route(&router, db, imp_user, bookmarks).await {
+                Ok(table) => {
+                    info!("fetched routing table of database {:?}: {:?}", db, table);
+                    return Ok(table);
+                }
+                Err(e @ Error::AuthenticationError(_)) | Err(e @ Error::UnsupportedVersion(_)) => {
+                    return Err(e)
+                }
+                Err(Error::Neo4jError(e)) if !e.is_transient() => return Err(Error::Neo4jError(e)),
+                Err(e) => warn!("could not fetch routing table from {}: {}", router, e),
+            }
+        }
+        Err(Error::ServiceUnavailable(format!(
+            "no router could provide a routing table for database {:?}",
+            db
+        )))
+    }
+
+    async fn route(
+        &self,
+        router: &str,
+        db: &str,
+        imp_user: Option<&str>,
+        bookmarks: &[String],
+    ) -> Result<RoutingTable> {
+        let mut connection = acquire(&self.pool(router)?).await?;
+        let routing = self.info.routing_context().cloned().unwrap_or_default();
+        let route = BoltRequest::route(connection.version(), routing, bookmarks, db, imp_user);
+        match connection.send_recv(route).await? {
+            BoltResponse::Success(success) => {
+                let rt = success.get("rt").ok_or_else(|| {
+                    Error::UnexpectedMessage("ROUTE response without routing table".into())
+                })?;
+                RoutingTable::parse(rt, Instant::now())
+            }
+            BoltResponse::Failure(failure) => Err(failure.into_error()),
+            msg => Err(unexpected(msg, "ROUTE")),
+        }
+    }
+
+    async fn forget(&self, address: &str) {
+        info!("forgetting unreachable server {}", address);
+        for table in self.tables.lock().await.values_mut() {
+            table.forget(address);
+        }
+        if let Some(pool) = self.pools.lock().unwrap().remove(address) {
+            pool.close();
+        }
+    }
+
+    /// The pools of the members the router knows of
+    pub fn pools(&self) -> Vec<ConnectionPool> {
+        self.pools.lock().unwrap().values().cloned().collect()
+    }
+
+    fn pool(&self, address: &str) -> Result<ConnectionPool> {
+        let mut pools = self.pools.lock().unwrap();
+        if let Some(pool) = pools.get(address) {
+            return Ok(pool.clone());
+        }
+        let pool = build_pool(self.info.with_address(address)?, &self.pool_config)?;
+        pools.insert(address.to_owned(), pool.clone());
+        Ok(pool)
+    }
+}
+
+/// Whether `error` means the server couldn't be reached, as opposed to the server rejecting the
+/// connection, e.g. because of invalid credentials
+fn is_unreachable(error: &Error) -> bool {
+    matches!(
+        error,
+        Error::ConnectionError | Error::IOError { .. } | Error::ConnectTimeout(_)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::AuthToken;
+    use crate::connection::tests::{
+        failure, ignored, recording_stub_server, request_bytes, serve, success, success_with,
+    };
+    use crate::pool::tests::pool_config;
+    use crate::transport::TlsConfig;
+    use crate::version::Version;
+    use bytes::Bytes;
+    use tokio::net::TcpListener;
+
+    fn table(ttl: i64, servers: &[(&str, &[&str])]) -> BoltMap {
+        let servers: Vec<BoltType> = servers
+            .iter()
+            .map(|(role, addresses)| {
+                let addresses: Vec<BoltType> = addresses.iter().map(|&a| a.into()).collect();
+                let mut server = BoltMap::default();
+                server.put("role".into(), (*role).into());
+                server.put("addresses".into(), addresses.into());
+                BoltType::Map(server)
+            })
+            .collect();
+        let mut rt = BoltMap::default();
+        rt.put("ttl".into(), ttl.into());
+        rt.put("servers".into(), servers.into());
+        rt
+    }
+
+    fn route_success(rt: BoltMap) -> Bytes {
+        let mut metadata = BoltMap::default();
+        metadata.put("rt".into(), BoltType::Map(rt));
+        success_with(metadata)
+    }
+
+    async fn listen() -> (TcpListener, String) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        (listener, address)
+    }
+
+    /// A cluster member accepting any number of connections, each of them is served the script
+    fn serve_member(listener: TcpListener, script: Vec<Vec<Bytes>>) {
+        tokio::spawn(async move {
+            loop {
+                let (stream, _) = listener.accept().await.unwrap();
+                tokio::spawn(serve(stream, script.clone()));
+            }
+        });
+    }
+
+    async fn member(script: Vec<Vec<Bytes>>) -> String {
+        let (listener, address) = listen().await;
+        serve_member(listener, script);
+        address
+    }
+
+    /// An address nothing listens on
+    async fn unreachable() -> String {
+        listen().await.1
+    }
+
+    fn router(address: &str) -> Router {
+        let uri = format!("neo4j://{}", address);
+        let info = ConnectionInfo::new(
+            &uri,
+            Arc::new(AuthToken::basic("user", "pass")),
+            &TlsConfig::default(),
+        )
+        .unwrap();
+        Router::new(info, pool_config(4))
+    }
+
+    fn address(connection: &ManagedConnection) -> String {
+        server_address(connection).unwrap()
+    }
+
+    #[test]
+    fn should_parse_routing_tables() {
+        let now = Instant::now();
+        let rt = table(
+            300,
+            &[
+                ("WRITE", &["w:7687"]),
+                ("READ", &["r1:7687", "r2:7687"]),
+                ("ROUTE", &["w:7687", "r1:7687"]),
+            ],
+        );
+
+        let mut table = RoutingTable::parse(rt, now).unwrap();
+
+        assert_eq!(table.servers(AccessMode::Write), ["w:7687"]);
+        assert_eq!(table.servers(AccessMode::Read), ["r1:7687", "r2:7687"]);
+        assert_eq!(table.routers, ["w:7687", "r1:7687"]);
+        assert!(table.is_fresh(AccessMode::Read, now));
+        assert!(!table.is_fresh(AccessMode::Read, now + Duration::from_secs(300)));
+
+        table.forget_writer("w:7687");
+        assert!(!table.is_fresh(AccessMode::Write, now));
+        assert!(table.is_fresh(AccessMode::Read, now));
+        table.forget("r1:7687");
+        assert_eq!(table.servers(AccessMode::Read), ["r2:7687"]);
+        assert_eq!(table.routers, ["w:7687"]);
+    }
+
+    #[test]
+    fn should_cap_huge_ttls() {
+        let now = Instant::now();
+        let rt = table(i64::MAX, &[("WRITE", &["w:7687"]), ("ROUTE", &["w:7687"])]);
+
+        let table = RoutingTable::parse(rt, now).unwrap();
+
+        assert!(table.is_fresh(AccessMode::Write, now + Duration::from_secs(300)));
+    }
+
+    #[test]
+    fn should_reject_invalid_routing_tables() {
+        let mut rt = BoltMap::default();
+        rt.put("ttl".into(), 300.into());
+        assert!(RoutingTable::parse(rt, Instant::now()).is_err());
+    }
+
+    #[tokio::test]
+    async fn should_route_reads_and_writes() {
+        let writer = member(vec![vec![success()]]).await;
+        let reader = member(vec![vec![success()]]).await;
+        let gone = unreachable().await;
+        let rt = table(
+            300,
+            &[
+                ("WRITE", &[&writer]),
+                ("READ", &[&gone, &reader]),
+                ("ROUTE", &[&gone]),
+            ],
+        );
+        let seed = member(vec![vec![success()], vec![route_success(rt)]]).await;
+        let router = router(&seed);
+
+        let write = router
+            .acquire(AccessMode::Write, "", None, &[])
+            .await
+            .unwrap();
+        assert_eq!(address(&write), writer);
+        for _ in 0..2 {
+            let read = router
+                .acquire(AccessMode::Read, "", None, &[])
+                .await
+                .unwrap();
+            assert_eq!(address(&read), reader);
+        }
+
+        let tables = router.tables.lock().await;
+        assert_eq!(
+            tables[&("".to_owned(), None)].servers(AccessMode::Read),
+            [reader]
+        );
+        assert!(tables[&("".to_owned(), None)].routers.is_empty());
+    }
+
+    #[tokio::test]
+    async fn should_send_the_bookmarks_along_with_route() {
+        let writer = member(vec![vec![success()]]).await;
+        let (seed, requests) = recording_stub_server(vec![
+            vec![success()],
+            vec![route_success(table(300, &[("WRITE", &[&writer])]))],
+        ])
+        .await;
+        let router = router(&seed);
+        let bookmarks = ["bookmark-1".to_owned()];
+
+        let write = router
+            .acquire(AccessMode::Write, "movies", None, &bookmarks)
+            .await
+            .unwrap();
+        assert_eq!(address(&write), writer);
+
+        let routing = router.info.routing_context().cloned().unwrap_or_default();
+        let route = BoltRequest::route(Version::V4_4, routing, &bookmarks, "movies", None);
+        assert_eq!(requests.lock().unwrap()[1], request_bytes(route));
+    }
+
+    #[tokio::test]
+    async fn should_keep_members_rejecting_the_credentials() {
+        let writer = member(vec![vec![failure("Neo.ClientError.Security.Unauthorized")]]).await;
+        let seed = member(vec![
+            vec![success()],
+            vec![route_success(table(300, &[("WRITE", &[&writer])]))],
+        ])
+        .await;
+        let router = router(&seed);
+
+        assert!(matches!(
+            router.acquire(AccessMode::Write, "", None, &[]).await,
+            Err(Error::AuthenticationError(_))
+        ));
+        let tables = router.tables.lock().await;
+        assert_eq!(
+            tables[&("".to_owned(), None)].servers(AccessMode::Write),
+            [writer]
+        );
+    }
+
+    #[tokio::test]
+    async fn should_keep_a_routing_table_per_impersonated_user() {
+        let writer = member(vec![vec![success()]]).await;
+        let alices_writer = member(vec![vec![success()]]).await;
+        let seed = member(vec![
+            vec![success()],
+            vec![route_success(table(300, &[("WRITE", &[&writer])]))],
+            vec![route_success(table(300, &[("WRITE", &[&alices_writer])]))],
+        ])
+        .await;
+        let router = router(&seed);
+
+        let write = router
+            .acquire(AccessMode::Write, "", None, &[])
+            .await
+            .unwrap();
+        assert_eq!(address(&write), writer);
+        let write = router
+            .acquire(AccessMode::Write, "", Some("alice"), &[])
+            .await
+            .unwrap();
+        assert_eq!(address(&write), alices_writer);
+
+        let tables = router.tables.lock().await;
+        let key = ("".to_owned(), Some("alice".to_owned()));
+        assert_eq!(tables[&key].servers(AccessMode::Write), [alices_writer]);
+        assert_eq!(tables.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn should_refresh_expired_tables() {
+        let first = member(vec![vec![success()]]).await;
+        let second = member(vec![vec![success()]]).await;
+        let (listener, seed) = listen().await;
+        serve_member(
+            listener,
+            vec![
+                vec![success()],
+                vec![route_success(table(
+                    0,
+                    &[("WRITE", &[&first]), ("ROUTE", &[&seed])],
+                ))],
+                vec![route_success(table(
+                    0,
+                    &[("WRITE", &[&second]), ("ROUTE", &[&seed])],
+                ))],
+            ],
+        );
+        let router = router(&seed);
+
+        let write = router
+            .acquire(AccessMode::Write, "movies", None, &[])
+            .await
+            .unwrap();
+        assert_eq!(address(&write), first);
+        let write = router
+            .acquire(AccessMode::Write, "movies", None, &[])
+            .await
+            .unwrap();
+        assert_eq!(address(&write), second);
+    }
+
+    #[tokio::test]
+    async fn should_not_lock_the_routing_tables_during_a_refresh() {
+        let seed = member(vec![vec![success()], vec![]]).await;
+        let router = Arc::new(router(&seed));
+        let refreshing = router.clone();
+        let refresh = tokio::spawn(async move {
+            drop(refreshing.acquire(AccessMode::Write, "", None, &[]).await);
+        });
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        let error = Error::Neo4jError(Neo4jError::new(NOT_A_LEADER.into(), "".into()));
+        tokio::time::timeout(Duration::from_secs(1), router.on_error(&seed, &error))
+            .await
+            .unwrap();
+        refresh.abort();
+    }
+
+    #[tokio::test]
+    async fn should_fail_over_when_the_leader_changes() {
+        let old = member(vec![vec![success()]]).await;
+        let new = member(vec![vec![success()]]).await;
+        let (listener, seed) = listen().await;
+        serve_member(
+            listener,
+            vec![
+                vec![success()],
+                vec![route_success(table(
+                    300,
+                    &[("WRITE", &[&old]), ("ROUTE", &[&seed])],
+                ))],
+                vec![route_success(table(
+                    300,
+                    &[("WRITE", &[&new]), ("ROUTE", &[&seed])],
+                ))],
+            ],
+        );
+        let router = router(&seed);
+
+        let write = router
+            .acquire(AccessMode::Write, "", None, &[])
+            .await
+            .unwrap();
+        assert_eq!(address(&write), old);
+        let error = Error::Neo4jError(Neo4jError::new(NOT_A_LEADER.into(), "".into()));
+        router.on_error(&old, &error).await;
+
+        let write = router
+            .acquire(AccessMode::Write, "", None, &[])
+            .await
+            .unwrap();
+        assert_eq!(address(&write), new);
+    }
+
+    #[tokio::test]
+    async fn should_retry_transaction_functions_on_the_new_leader() {
+        let old = member(vec![
+            vec![success()],
+            vec![success()],
+            vec![failure(NOT_A_LEADER)],
+            vec![ignored()],
+            vec![success()],
+        ])
+        .await;
+        let new = member(vec![
+            vec![success()],
+            vec![success()],
+            vec![success()],
+            vec![success()],
+            vec![success()],
+        ])
+        .await;
+        let reader = member(vec![vec![success()]]).await;
+        let (listener, seed) = listen().await;
+        let first = table(
+            300,
+            &[
+                ("WRITE", &[&old]),
+                ("READ", &[&reader]),
+                ("ROUTE", &[&seed]),
+            ],
+        );
+        let second = table(
+            300,
+            &[
+                ("WRITE", &[&new]),
+                ("READ", &[&reader]),
+                ("ROUTE", &[&seed]),
+            ],
+        );
+        serve_member(
+            listener,
+            vec![
+                vec![success()],
+                vec![route_success(first)],
+                vec![route_success(second)],
+            ],
+        );
+        let config = crate::config()
+            .uri(&format!("neo4j://{}", seed))
+            .user("user")
+            .password("pass")
+            .build()
+            .unwrap();
+        let graph = crate::Graph::connect(config).await.unwrap();
+        let servers = std::sync::Mutex::new(Vec::new());
+
+        graph
+            .execute_write(|txn| {
+                let servers = &servers;
+                async move {
+                    let address = txn.server_address().await.unwrap();
+                    servers.lock().unwrap().push(address);
+                    txn.run(crate::query("CREATE ()")).await
+                }
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(*servers.lock().unwrap(), [old, new]);
+    }
+
+    #[tokio::test]
+    async fn should_open_min_idle_connections_to_each_reader_and_writer() {
+        let writer = member(vec![vec![success()]]).await;
+        let first = member(vec![vec![success()]]).await;
+        let second = member(vec![vec![success()]]).await;
+        let (listener, seed) = listen().await;
+        let rt = table(
+            300,
+            &[
+                ("WRITE", &[&writer]),
+                ("READ", &[&first, &second]),
+                ("ROUTE", &[&seed]),
+            ],
+        );
+        serve_member(listener, vec![vec![success()], vec![route_success(rt)]]);
+        let config = crate::config()
+            .uri(&format!("neo4j://{}", seed))
+            .user("user")
+            .password("pass")
+            .min_idle(2)
+            .build()
+            .unwrap();
+
+        let graph = crate::Graph::connect(config).await.unwrap();
+
+        let metrics = graph.metrics();
+        for member in [writer, first, second] {
+            let pool = metrics.iter().find(|m| m.address == member).unwrap();
+            assert_eq!((pool.in_use, pool.idle), (0, 2));
+        }
+    }
+
+    #[tokio::test]
+    async fn should_report_unavailable_clusters() {
+        let router = router(&unreachable().await);
+        assert!(matches!(
+            router.acquire(AccessMode::Read, "", None, &[]).await,
+            Err(Error::ServiceUnavailable(_))
+        ));
+
+        let seed = member(vec![
+            vec![success()],
+            vec![failure("Neo.ClientError.Database.DatabaseNotFound")],
+        ])
+        .await;
+        let router = self::router(&seed);
+        assert!(matches!(
+            router.acquire(AccessMode::Read, "missing", None, &[]).await,
+            Err(Error::Neo4jError(_))
+        ));
+    }
+}
@@ -1,3 +1,4 @@
+use crate::errors::Result;
 use crate::types::*;
 use std::convert::TryInto;
 
@@ -120,6 +121,12 @@ impl Row {
     pub fn get<T: std::convert::TryFrom<BoltType>>(&self, key: &str) -> Option<T> {
         self.attributes.get(key)
     }
+
+    /// Deserialize the whole row into a type implementing [`serde::Deserialize`], with one field
+    /// per returned column, see [`BoltType::to`] for how values are mapped
+    pub fn to<'this, T: serde::Deserialize<'this>>(&'this self) -> Result<T> {
+        self.attributes.to()
+    }
 }
 
 impl Node {
@@ -132,6 +139,12 @@ impl Node {
         self.inner.id.value
     }
 
+    /// Element id of the node, servers speaking bolt versions older than 5.0 don't send element
+    /// ids, in which case this is the [`Node::id`] as a string
+    pub fn element_id(&self) -> &str {
+        &self.inner.element_id.value
+    }
+
     /// various labels attached to this node
     pub fn labels(&self) -> Vec<String> {
         self.inner.labels.iter().map(|l| l.to_string()).collect()
@@ -141,6 +154,11 @@ impl Node {
     pub fn get<T: std::convert::TryFrom<BoltType>>(&self, key: &str) -> Option<T> {
         self.inner.get(key)
     }
+
+    /// Deserialize the properties into a type implementing [`serde::Deserialize`]
+    pub fn to<'this, T: serde::Deserialize<'this>>(&'this self) -> Result<T> {
+        self.inner.properties.to()
+    }
 }
 
 impl Relation {
@@ -160,6 +178,20 @@ impl Relation {
         self.inner.end_node_id.value
     }
 
+    /// Element id of the relationship, this is the [`Relation::id`] as a string for servers
+    /// speaking bolt versions older than 5.0
+    pub fn element_id(&self) -> &str {
+        &self.inner.element_id.value
+    }
+
+    pub fn start_node_element_id(&self) -> &str {
+        &self.inner.start_node_element_id.value
+    }
+
+    pub fn end_node_element_id(&self) -> &str {
+        &self.inner.end_node_element_id.value
+    }
+
     pub fn typ(&self) -> String {
         self.inner.typ.value.clone()
     }
@@ -167,6 +199,11 @@ impl Relation {
     pub fn get<T: std::convert::TryFrom<BoltType>>(&self, key: &str) -> Option<T> {
         self.inner.get(key)
     }
+
+    /// Deserialize the properties into a type implementing [`serde::Deserialize`]
+    pub fn to<'this, T: serde::Deserialize<'this>>(&'this self) -> Result<T> {
+        self.inner.properties.to()
+    }
 }
 
 impl UnboundedRelation {
@@ -178,6 +215,12 @@ impl UnboundedRelation {
         self.inner.id.value
     }
 
+    /// Element id of the relationship, this is the [`UnboundedRelation::id`] as a string for
+    /// servers speaking bolt versions older than 5.0
+    pub fn element_id(&self) -> &str {
+        &self.inner.element_id.value
+    }
+
     pub fn typ(&self) -> String {
         self.inner.typ.value.clone()
     }
@@ -185,4 +228,9 @@ impl UnboundedRelation {
     pub fn get<T: std::convert::TryFrom<BoltType>>(&self, key: &str) -> Option<T> {
         self.inner.get(key)
     }
+
+    /// Deserialize the properties into a type implementing [`serde::Deserialize`]
+    pub fn to<'this, T: serde::Deserialize<'this>>(&'this self) -> Result<T> {
+        self.inner.properties.to()
+    }
 }
@@ -0,0 +1,619 @@
+use crate::errors::*;
+use crate::types::*;
+use chrono::{DateTime, FixedOffset, NaiveDate, NaiveDateTime, NaiveTime};
+use serde::ser::{self, Serialize};
+use std::convert::TryFrom;
+
+const DATE: &str = "$neo4rs::Date";
+const LOCAL_TIME: &str = "$neo4rs::LocalTime";
+const LOCAL_DATE_TIME: &str = "$neo4rs::LocalDateTime";
+const DATE_TIME: &str = "$neo4rs::DateTime";
+
+impl BoltType {
+    /// Serialize any type implementing [`serde::Serialize`] into a bolt value.
+    ///
+    /// Structs and maps become maps, sequences and tuples become lists, `None` and unit become
+    /// null, unit enum variants become their name as a string and other variants a map with the
+    /// variant name as the single key. Chrono dates, times and datetimes, including
+    /// `DateTime<Utc>`, become temporal values, while strings stay strings even if they look
+    /// like a date. The [`crate::temporal`] modules pick the temporal type explicitly.
+    pub fn from_serialize<T: Serialize + ?Sized>(value: &T) -> Result<BoltType> {
+        value.serialize(BoltTypeSerializer)
+    }
+}
+
+impl ser::Error for Error {
+    fn custom<T: std::fmt::Display>(msg: T) -> Self {
+        Error::SerializationError(msg.to_string())
+    }
+}
+
+pub struct BoltTypeSerializer;
+
+impl ser::Serializer for BoltTypeSerializer {
+    type Ok = BoltType;
+    type Error = Error;
+    type SerializeSeq = SerializeList;
+    type SerializeTuple = SerializeList;
+    type SerializeTupleStruct = SerializeList;
+    type SerializeTupleVariant = SerializeVariant<SerializeList>;
+    type SerializeMap = SerializeMap;
+    type SerializeStruct = SerializeMap;
+    type SerializeStructVariant = SerializeVariant<SerializeMap>;
+
+    fn serialize_bool(self, v: bool) -> Result<BoltType> {
+        Ok(v.into())
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<BoltType> {
+        Ok(v.into())
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<BoltType> {
+        Ok(v.into())
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<BoltType> {
+        Ok(v.into())
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<BoltType> {
+        Ok(v.into())
+    }
+
+    fn serialize_i128(self, v: i128) -> Result<BoltType> {
+        BoltType::try_from(v).map_err(|_| out_of_range(v))
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<BoltType> {
+        Ok(i64::from(v).into())
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<BoltType> {
+        Ok(v.into())
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<BoltType> {
+        Ok(v.into())
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<BoltType> {
+        BoltType::try_from(v).map_err(|_| out_of_range(v))
+    }
+
+    fn serialize_u128(self, v: u128) -> Result<BoltType> {
+        BoltType::try_from(v).map_err(|_| out_of_range(v))
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<BoltType> {
+        Ok(v.into())
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<BoltType> {
+        Ok(v.into())
+    }
+
+    fn serialize_char(self, v: char) -> Result<BoltType> {
+        Ok(v.to_string().into())
+    }
+
+    fn serialize_str(self, v: &str) -> Result<BoltType> {
+        Ok(v.into())
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<BoltType> {
+        Ok(v.into())
+    }
+
+    /// Chrono serializes its values through `collect_str`, unlike `String` and `&str`, so values
+    /// collected here that parse as a temporal value are sent as one.
+    fn collect_str<T: std::fmt::Display + ?Sized>(self, value: &T) -> Result<BoltType> {
+        let value = value.to_string();
+        Ok(detect_temporal(&value).unwrap_or_else(|| value.into()))
+    }
+
+    fn serialize_none(self) -> Result<BoltType> {
+        Ok(BoltType::Null(BoltNull))
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<BoltType> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<BoltType> {
+        Ok(BoltType::Null(BoltNull))
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<BoltType> {
+        self.serialize_unit()
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<BoltType> {
+        Ok(variant.into())
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        name: &'static str,
+        value: &T,
+    ) -> Result<BoltType> {
+        match name {
+            DATE | LOCAL_TIME | LOCAL_DATE_TIME | DATE_TIME => {
+                let value = String::try_from(value.serialize(self)?).map_err(|_| {
+                    Error::SerializationError("temporal values must be strings".into())
+                })?;
+                parse_temporal(name, &value)
+            }
+            _ => value.serialize(self),
+        }
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<BoltType> {
+        let mut map = BoltMap::with_capacity(1);
+        map.put(variant.into(), value.serialize(self)?);
+        Ok(BoltType::Map(map))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<SerializeList> {
+        Ok(SerializeList {
+            items: Vec::with_capacity(len.unwrap_or(0)),
+        })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<SerializeList> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(self, _name: &'static str, len: usize) -> Result<SerializeList> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<SerializeVariant<SerializeList>> {
+        Ok(SerializeVariant {
+            variant,
+            inner: self.serialize_seq(Some(len))?,
+        })
+    }
+
+    fn serialize_map(self, len: Option<usize>) -> Result<SerializeMap> {
+        Ok(SerializeMap {
+            map: BoltMap::with_capacity(len.unwrap_or(0)),
+            key: None,
+        })
+    }
+
+    fn serialize_struct(self, _name: &'static str, len: usize) -> Result<SerializeMap> {
+        self.serialize_map(Some(len))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<SerializeVariant<SerializeMap>> {
+        Ok(SerializeVariant {
+            variant,
+            inner: self.serialize_map(Some(len))?,
+        })
+    }
+}
+
+fn out_of_range<T: std::fmt::Display>(value: T) -> Error {
+    Error::SerializationError(format!("{} does not fit into a 64 bit integer", value))
+}
+
+fn parse_temporal(name: &str, value: &str) -> Result<BoltType> {
+    let invalid = |e: chrono::ParseError| {
+        Error::SerializationError(format!("invalid temporal value {}: {}", value, e))
+    };
+    match name {
+        DATE => value.parse::<NaiveDate>().map(Into::into).map_err(invalid),
+        LOCAL_TIME => value.parse::<NaiveTime>().map(Into::into).map_err(invalid),
+        LOCAL_DATE_TIME => value
+            .parse::<NaiveDateTime>()
+            .map(Into::into)
+            .map_err(invalid),
+        _ => DateTime::<FixedOffset>::parse_from_rfc3339(value)
+            .map(Into::into)
+            .map_err(invalid),
+    }
+}
+
+/// The temporal value `value` is the ISO 8601 representation of, if any
+fn detect_temporal(value: &str) -> Option<BoltType> {
+    DateTime::<FixedOffset>::parse_from_rfc3339(value)
+        .map(Into::into)
+        .or_else(|_| value.parse::<NaiveDateTime>().map(Into::into))
+        .or_else(|_| value.parse::<NaiveDate>().map(Into::into))
+        .or_else(|_| value.parse::<NaiveTime>().map(Into::into))
+        .ok()
+}
+
+pub struct SerializeList {
+    items: Vec<BoltType>,
+}
+
+impl ser::SerializeSeq for SerializeList {
+    type Ok = BoltType;
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        self.items.push(value.serialize(BoltTypeSerializer)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<BoltType> {
+        Ok(BoltType::List(BoltList { value: self.items }))
+    }
+}
+
+impl ser::SerializeTuple for SerializeList {
+    type Ok = BoltType;
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<BoltType> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+impl ser::SerializeTupleStruct for SerializeList {
+    type Ok = BoltType;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<BoltType> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+pub struct SerializeMap {
+    map: BoltMap,
+    key: Option<BoltString>,
+}
+
+impl ser::SerializeMap for SerializeMap {
+    type Ok = BoltType;
+    type Error = Error;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<()> {
+        let key = match key.serialize(BoltTypeSerializer)? {
+            BoltType::String(s) => s,
+            BoltType::Integer(i) => i.value.to_string().into(),
+            BoltType::Boolean(b) => b.value.to_string().into(),
+            _ => {
+                return Err(Error::SerializationError(
+                    "map keys must be strings, integers or booleans".into(),
+                ))
+            }
+        };
+        self.key = Some(key);
+        Ok(())
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        let key = self
+            .key
+            .take()
+            .ok_or_else(|| Error::SerializationError("value serialized before key".into()))?;
+        let value = value.serialize(BoltTypeSerializer).map_err(|e| match e {
+            Error::SerializationError(msg) => {
+                Error::SerializationError(format!("{}: {}", key, msg))
+            }
+            e => e,
+        })?;
+        self.map.put(key, value);
+        Ok(())
+    }
+
+    fn end(self) -> Result<BoltType> {
+        Ok(BoltType::Map(self.map))
+    }
+}
+
+impl ser::SerializeStruct for SerializeMap {
+    type Ok = BoltType;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<()> {
+        ser::SerializeMap::serialize_entry(self, key, value)
+    }
+
+    fn end(self) -> Result<BoltType> {
+        ser::SerializeMap::end(self)
+    }
+}
+
+/// Wraps the serialized fields of a tuple or struct variant in a map keyed by the variant name
+pub struct SerializeVariant<S> {
+    variant: &'static str,
+    inner: S,
+}
+
+impl<S> SerializeVariant<S> {
+    fn wrap(variant: &'static str, value: BoltType) -> BoltType {
+        let mut map = BoltMap::with_capacity(1);
+        map.put(variant.into(), value);
+        BoltType::Map(map)
+    }
+}
+
+impl ser::SerializeTupleVariant for SerializeVariant<SerializeList> {
+    type Ok = BoltType;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        ser::SerializeSeq::serialize_element(&mut self.inner, value)
+    }
+
+    fn end(self) -> Result<BoltType> {
+        let value = ser::SerializeSeq::end(self.inner)?;
+        Ok(Self::wrap(self.variant, value))
+    }
+}
+
+impl ser::SerializeStructVariant for SerializeVariant<SerializeMap> {
+    type Ok = BoltType;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<()> {
+        ser::SerializeMap::serialize_entry(&mut self.inner, key, value)
+    }
+
+    fn end(self) -> Result<BoltType> {
+        let value = ser::SerializeMap::end(self.inner)?;
+        Ok(Self::wrap(self.variant, value))
+    }
+}
+
+/// Serde `with` modules sending chrono values as bolt temporal values. Chrono values are detected
+/// without them, the modules spell out the bolt type of a field.
+///
+/// ```
+/// #[derive(serde::Serialize, serde::Deserialize)]
+/// struct Person {
+///     name: String,
+///     #[serde(with = "neo4rs::temporal::date")]
+///     born: chrono::NaiveDate,
+/// }
+/// ```
+///
+/// Other serializers, e.g. `serde_json`, still see the ISO 8601 string.
+pub mod temporal {
+    macro_rules! temporal {
+        ($(#[$doc:meta])* $module:ident, $ty:ty, $name:expr, $format:expr) => {
+            $(#[$doc])*
+            pub mod $module {
+                use serde::{Deserialize, Deserializer, Serializer};
+
+                pub fn serialize<S: Serializer>(
+                    value: &$ty,
+                    serializer: S,
+                ) -> ::std::result::Result<S::Ok, S::Error> {
+                    let format: fn(&$ty) -> String = $format;
+                    serializer.serialize_newtype_struct($name, &format(value))
+                }
+
+                pub fn deserialize<'de, D: Deserializer<'de>>(
+                    deserializer: D,
+                ) -> ::std::result::Result<$ty, D::Error> {
+                    <$ty>::deserialize(deserializer)
+                }
+            }
+        };
+    }
+
+    temporal!(
+        /// `chrono::NaiveDate` as a bolt date
+        date,
+        chrono::NaiveDate,
+        crate::ser::DATE,
+        |v| v.format("%Y-%m-%d").to_string()
+    );
+    temporal!(
+        /// `chrono::NaiveTime` as a bolt local time
+        local_time,
+        chrono::NaiveTime,
+        crate::ser::LOCAL_TIME,
+        |v| v.format("%H:%M:%S%.f").to_string()
+    );
+    temporal!(
+        /// `chrono::NaiveDateTime` as a bolt local datetime
+        local_datetime,
+        chrono::NaiveDateTime,
+        crate::ser::LOCAL_DATE_TIME,
+        |v| v.format("%Y-%m-%dT%H:%M:%S%.f").to_string()
+    );
+    temporal!(
+        /// `chrono::DateTime<FixedOffset>` as a bolt datetime
+        datetime,
+        chrono::DateTime<chrono::FixedOffset>,
+        crate::ser::DATE_TIME,
+        |v| v.to_rfc3339()
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use serde::Serialize;
+    use std::collections::HashMap;
+
+    #[derive(Serialize)]
+    struct Person {
+        name: String,
+        age: u8,
+        nickname: Option<String>,
+        tags: Vec<&'static str>,
+    }
+
+    fn map(entries: Vec<(&str, BoltType)>) -> BoltType {
+        BoltType::Map(entries.into_iter().map(|(k, v)| (k.into(), v)).collect())
+    }
+
+    #[test]
+    fn should_serialize_a_struct() {
+        let person = Person {
+            name: "Mark".into(),
+            age: 42,
+            nickname: None,
+            tags: vec!["a"],
+        };
+
+        assert_eq!(
+            BoltType::from_serialize(&person).unwrap(),
+            map(vec![
+                ("name", "Mark".into()),
+                ("age", 42.into()),
+                ("nickname", BoltType::Null(BoltNull)),
+                ("tags", vec!["a"].into()),
+            ])
+        );
+    }
+
+    #[test]
+    fn should_serialize_collections() {
+        let mut scores = HashMap::new();
+        scores.insert(1, 1.5);
+
+        assert_eq!(
+            BoltType::from_serialize(&scores).unwrap(),
+            map(vec![("1", 1.5.into())])
+        );
+        assert_eq!(
+            BoltType::from_serialize(&(1, "a")).unwrap(),
+            BoltType::List(BoltList {
+                value: vec![1.into(), "a".into()]
+            })
+        );
+        assert!(BoltType::from_serialize(&u64::MAX).is_err());
+    }
+
+    #[test]
+    fn should_serialize_enums() {
+        #[derive(Serialize)]
+        enum Shape {
+            Empty,
+            Circle(f64),
+            Line(i64, i64),
+            Rect { w: i64 },
+        }
+
+        assert_eq!(
+            BoltType::from_serialize(&Shape::Empty).unwrap(),
+            "Empty".into()
+        );
+        assert_eq!(
+            BoltType::from_serialize(&Shape::Circle(1.0)).unwrap(),
+            map(vec![("Circle", 1.0.into())])
+        );
+        assert_eq!(
+            BoltType::from_serialize(&Shape::Line(1, 2)).unwrap(),
+            map(vec![("Line", vec![1, 2].into())])
+        );
+        assert_eq!(
+            BoltType::from_serialize(&Shape::Rect { w: 3 }).unwrap(),
+            map(vec![("Rect", map(vec![("w", 3.into())]))])
+        );
+    }
+
+    #[test]
+    fn should_serialize_temporal_values() {
+        #[derive(Serialize)]
+        struct Event {
+            #[serde(with = "temporal::date")]
+            day: NaiveDate,
+            #[serde(with = "temporal::local_time")]
+            at: NaiveTime,
+            #[serde(with = "temporal::local_datetime")]
+            local: NaiveDateTime,
+            #[serde(with = "temporal::datetime")]
+            zoned: DateTime<FixedOffset>,
+            plain: NaiveDate,
+            utc: DateTime<Utc>,
+            naive: NaiveDateTime,
+            text: String,
+        }
+
+        let day = NaiveDate::from_ymd_opt(2021, 6, 27).unwrap();
+        let at = NaiveTime::from_hms_nano_opt(10, 15, 30, 200).unwrap();
+        let local = NaiveDateTime::new(day, at);
+        let zoned = DateTime::parse_from_rfc3339("2021-06-27T10:15:30.5+01:00").unwrap();
+        let event = Event {
+            day,
+            at,
+            local,
+            zoned,
+            plain: day,
+            utc: zoned.with_timezone(&Utc),
+            naive: local,
+            text: "2021-06-27".into(),
+        };
+
+        assert_eq!(
+            BoltType::from_serialize(&event).unwrap(),
+            map(vec![
+                ("day", day.into()),
+                ("at", at.into()),
+                ("local", local.into()),
+                ("zoned", zoned.into()),
+                ("plain", day.into()),
+                (
+                    "utc",
+                    zoned
+                        .with_timezone(&FixedOffset::east_opt(0).unwrap())
+                        .into()
+                ),
+                ("naive", local.into()),
+                ("text", "2021-06-27".into()),
+            ])
+        );
+    }
+
+    #[test]
+    fn should_name_the_field_that_failed() {
+        #[derive(Serialize)]
+        struct Counter {
+            value: u64,
+        }
+
+        match BoltType::from_serialize(&Counter { value: u64::MAX }) {
+            Err(Error::SerializationError(msg)) => assert!(msg.starts_with("value: "), "{}", msg),
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,182 @@
+use crate::bookmarks::BookmarkManager;
+use crate::errors::*;
+use crate::graph::Graph;
+use crate::query::Query;
+use crate::routing::AccessMode;
+use crate::stream::RowStream;
+use crate::summary::ResultSummary;
+use crate::txn::{Txn, TxnConfig};
+use std::future::Future;
+
+/// Defaults for the work done through a [`Session`], fields which are not set fall back to the
+/// [`crate::Config`] of the graph.
+///
+/// ```
+/// use neo4rs::*;
+///
+/// let config = SessionConfig {
+///     db: Some("movies".to_owned()),
+///     access_mode: AccessMode::Read,
+///     ..SessionConfig::default()
+/// };
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SessionConfig {
+    /// The database queries and transactions run against
+    pub db: Option<String>,
+    /// Whether queries and transactions are routed to the readers or the leader of a cluster
+    pub access_mode: AccessMode,
+    /// Bookmarks the first query or transaction of the session waits for
+    pub bookmarks: Vec<String>,
+    /// The number of rows fetched at once by a [`RowStream`]
+    pub fetch_size: Option<usize>,
+    /// The user queries and transactions are executed as, requires Neo4j 4.4 or later and the
+    /// privilege to impersonate that user
+    pub impersonated_user: Option<String>,
+}
+
+/// A sequence of queries and transactions with common defaults, created by [`Graph::session`].
+///
+/// Sessions share the connection pool of their graph and are cheap to create. Every query or
+/// transaction of a session sees the changes committed by the ones before it, see
+/// [`Session::last_bookmarks`].
+pub struct Session {
+    graph: Graph,
+    access_mode: AccessMode,
+    bookmarks: BookmarkManager,
+}
+
+impl Session {
+    pub(crate) fn new(mut graph: Graph, config: SessionConfig) -> Session {
+        let bookmarks =
+            BookmarkManager::for_session(graph.config.bookmark_manager.take(), config.bookmarks);
+        if let Some(db) = config.db {
+            graph.config.db = db;
+        }
+        if let Some(fetch_size) = config.fetch_size {
+            graph.config.fetch_size = fetch_size;
+        }
+        graph.config.imp_user = config.impersonated_user;
+        graph.config.bookmark_manager = Some(bookmarks.clone());
+        Session {
+            graph,
+            access_mode: config.access_mode,
+            bookmarks,
+        }
+    }
+
+    /// Runs a query in its own transaction and discards the result, see [`Graph::run`]
+    pub async fn run(&self, q: Query) -> Result<()> {
+        self.graph.run_as(q, self.access_mode).await
+    }
+
+    /// Runs a query like [`Session::run`] and returns its [`ResultSummary`]
+    pub async fn run_with_summary(&self, q: Query) -> Result<ResultSummary> {
+        self.graph
+            .run_on(q, TxnConfig::new().mode(self.access_mode))
+            .await
+    }
+
+    /// Executes a query in its own transaction and returns a [`RowStream`], see
+    /// [`Graph::execute`]
+    pub async fn execute(&self, q: Query) -> Result<RowStream> {
+        self.graph.execute_as(q, self.access_mode).await
+    }
+
+    /// Starts a transaction in the access mode of the session
+    pub async fn start_txn(&self) -> Result<Txn> {
+        self.graph.start_txn_as(self.access_mode).await
+    }
+
+    /// Runs a transaction function in a read transaction, see [`Graph::execute_write`]
+    pub async fn execute_read<F, Fut, T>(&self, work: F) -> Result<T>
+    where
+        F: FnMut(Txn) -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        self.graph.execute_read(work).await
+    }
+
+    /// Runs a transaction function in a write transaction, see [`Graph::execute_write`]
+    pub async fn execute_write<F, Fut, T>(&self, work: F) -> Result<T>
+    where
+        F: FnMut(Txn) -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        self.graph.execute_write(work).await
+    }
+
+    /// The bookmarks of the last query or transaction committed by the session, or the bookmarks
+    /// the session was created with if nothing was committed yet. They can be passed to
+    /// [`SessionConfig::bookmarks`] of another session to make it see the changes of this one.
+    pub fn last_bookmarks(&self) -> Vec<String> {
+        self.bookmarks.own_bookmarks()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::connection::tests::{recording_stub_server, success, success_with};
+    use crate::types::BoltMap;
+    use crate::{config, query};
+    use bytes::Bytes;
+
+    fn bookmark(bookmark: &str) -> Bytes {
+        let mut metadata = BoltMap::default();
+        metadata.put("bookmark".into(), bookmark.into());
+        success_with(metadata)
+    }
+
+    fn contains(request: &Bytes, value: &str) -> bool {
+        request
+            .windows(value.len())
+            .any(|window| window == value.as_bytes())
+    }
+
+    #[tokio::test]
+    async fn should_send_session_defaults() {
+        let (uri, requests) = recording_stub_server(vec![
+            vec![success()],
+            vec![success()],
+            vec![bookmark("bookmark-1")],
+            vec![success()],
+            vec![bookmark("bookmark-2")],
+        ])
+        .await;
+        let config = config()
+            .uri(&uri)
+            .user("user")
+            .password("pass")
+            .max_connections(1)
+            .build()
+            .unwrap();
+        let graph = Graph::connect(config).await.unwrap();
+        let session = graph.session(SessionConfig {
+            db: Some("movies".into()),
+            access_mode: AccessMode::Read,
+            bookmarks: vec!["bookmark-0".into()],
+            fetch_size: None,
+            impersonated_user: Some("alice".into()),
+        });
+        assert_eq!(session.last_bookmarks(), ["bookmark-0"]);
+
+        session.run(query("MATCH (n) RETURN n")).await.unwrap();
+        assert_eq!(session.last_bookmarks(), ["bookmark-1"]);
+        let txn = session.start_txn().await.unwrap();
+        txn.commit().await.unwrap();
+        assert_eq!(session.last_bookmarks(), ["bookmark-2"]);
+
+        let requests = requests.lock().unwrap();
+        let (run, begin) = (&requests[1], &requests[3]);
+        for value in ["movies", "alice", "bookmark-0"] {
+            assert!(contains(run, value));
+        }
+        assert!(contains(run, "mode"));
+        for value in ["movies", "alice", "bookmark-1"] {
+            assert!(contains(begin, value));
+        }
+        assert!(contains(begin, "mode"));
+        assert!(!contains(begin, "bookmark-0"));
+    }
+}
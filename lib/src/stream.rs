@@ -1,9 +1,14 @@
+use crate::bookmarks::BookmarkManager;
 use crate::errors::*;
 use crate::messages::*;
 use crate::pool::*;
 use crate::row::*;
+use crate::summary::ResultSummary;
+use crate::trace::*;
 use crate::types::*;
+use futures::stream::{self, Stream};
 use std::collections::VecDeque;
+use std::convert::TryFrom;
 use std::sync::Arc;
 use tokio::sync::Mutex;
 
@@ -15,10 +20,17 @@ use tokio::sync::Mutex;
 pub struct RowStream {
     qid: i64,
     fields: BoltList,
+    t_first: Option<i64>,
+    summary: Option<ResultSummary>,
     state: State,
     fetch_size: usize,
     buffer: VecDeque<Row>,
     connection: Arc<Mutex<ManagedConnection>>,
+    bookmarks: Option<(BookmarkManager, Vec<String>)>,
+    db: String,
+    /// The span of the PULL whose rows are received, see [`RowStream::start_batch`]
+    batch: Span,
+    batch_rows: u64,
 }
 
 #[derive(Copy, Clone, PartialEq, Debug)]
@@ -30,48 +42,75 @@ enum State {
 }
 
 impl RowStream {
+    /// A stream whose first PULL was sent together with its RUN
     pub(crate) fn new(
         qid: i64,
         fields: BoltList,
+        t_first: Option<i64>,
         fetch_size: usize,
+        db: String,
         connection: Arc<Mutex<ManagedConnection>>,
     ) -> RowStream {
         RowStream {
             qid,
             fields,
+            t_first,
+            summary: None,
             connection,
             fetch_size,
-            state: State::Ready,
+            state: State::Streaming,
             buffer: VecDeque::with_capacity(fetch_size),
+            bookmarks: None,
+            db,
+            batch: Span::none(),
+            batch_rows: 0,
         }
     }
 
+    /// Opens the span of a PULL, which ends once its last row is received
+    pub(crate) fn start_batch(&mut self, connection: &ManagedConnection) {
+        self.batch = span!(
+            "PULL",
+            db.operation.name = "PULL",
+            db.namespace = namespace(&self.db),
+            neo4j.qid = self.qid,
+            db.response.returned_rows = Empty,
+            server.address = Empty,
+            server.port = Empty,
+        );
+        record_server(&self.batch, connection);
+        self.batch_rows = 0;
+    }
+
+    fn end_batch(&mut self) {
+        self.batch
+            .record("db.response.returned_rows", self.batch_rows);
+        self.batch = Span::none();
+    }
+
+    /// Passes the bookmark of an auto-commit query to `manager` once the result is complete,
+    /// `sent` are the bookmarks the query started from
+    pub(crate) fn track_bookmarks(&mut self, manager: BookmarkManager, sent: Vec<String>) {
+        self.bookmarks = Some((manager, sent));
+    }
+
     /// A call to next() will return a row from an internal buffer if the buffer has any entries,
     /// if the buffer is empty and the server has more rows left to consume, then a new batch of rows are fetched from the server (using the
     /// fetch_size value configured see [`crate::ConfigBuilder::fetch_size`])
     pub async fn next(&mut self) -> Result<Option<Row>> {
-        let mut connection = self.connection.lock().await;
+        let connection = self.connection.clone();
+        let mut connection = connection.lock().await;
         loop {
             match self.state {
                 State::Ready => {
                     let pull = BoltRequest::pull(self.fetch_size, self.qid);
-                    connection.send(pull).await?;
+                    if let Err(e) = connection.send_for(self.qid, pull).await {
+                        return Err(self.abort(e));
+                    }
+                    self.start_batch(&connection);
                     self.state = State::Streaming;
                 }
-                State::Streaming => match connection.recv().await {
-                    Ok(BoltResponse::Success(s)) => {
-                        if s.get("has_more").unwrap_or(false) {
-                            self.state = State::Buffered;
-                        } else {
-                            self.state = State::Complete;
-                        }
-                    }
-                    Ok(BoltResponse::Record(record)) => {
-                        let row = Row::new(self.fields.clone(), record.data);
-                        self.buffer.push_back(row);
-                    }
-                    msg => return Err(unexpected(msg, "PULL")),
-                },
+                State::Streaming => self.receive(&mut connection).await?,
                 State::Buffered => {
                     if !self.buffer.is_empty() {
                         return Ok(self.buffer.pop_front());
@@ -84,4 +123,90 @@ impl RowStream {
             }
         }
     }
+
+    /// Handles the next response to the PULL of this stream, other streams of the transaction may
+    /// send requests in between as the responses are matched by the query id
+    async fn receive(&mut self, connection: &mut ManagedConnection) -> Result<()> {
+        match connection.recv_for(self.qid).await {
+            Ok(BoltResponse::Success(s)) => {
+                self.end_batch();
+                if s.get("has_more").unwrap_or(false) {
+                    self.state = State::Buffered;
+                } else {
+                    self.complete(ResultSummary::new(&s, self.t_first));
+                }
+                Ok(())
+            }
+            Ok(BoltResponse::Record(record)) => {
+                let row = Row::new(self.fields.clone(), record.data);
+                self.buffer.push_back(row);
+                self.batch_rows += 1;
+                Ok(())
+            }
+            Ok(BoltResponse::Failure(failure)) => Err(self.abort(failure.into_error())),
+            Ok(msg) => Err(self.abort(unexpected(msg, "PULL"))),
+            Err(e) => Err(self.abort(e)),
+        }
+    }
+
+    /// Turns the row stream into a [`Stream`], so that combinators from
+    /// [`futures::StreamExt`] and [`futures::TryStreamExt`] can be used, e.g.
+    /// `stream.into_stream().try_collect::<Vec<Row>>()`
+    pub fn into_stream(self) -> impl Stream<Item = Result<Row>> {
+        stream::try_unfold(self, |mut rows| async move {
+            Ok(rows.next().await?.map(|row| (row, rows)))
+        })
+    }
+
+    /// Turns the row stream into a [`Stream`] of `T`, converting each row with [`TryFrom`]
+    pub fn into_stream_as<T: TryFrom<Row, Error = Error>>(self) -> impl Stream<Item = Result<T>> {
+        stream::try_unfold(self, |mut rows| async move {
+            match rows.next().await? {
+                Some(row) => Ok(Some((T::try_from(row)?, rows))),
+                None => Ok(None),
+            }
+        })
+    }
+
+    /// Discards any rows not consumed yet and returns the [`ResultSummary`] of the query.
+    pub async fn finish(mut self) -> Result<ResultSummary> {
+        if self.state != State::Complete {
+            let connection = self.connection.clone();
+            let mut connection = connection.lock().await;
+            // the rows of a PULL still in flight have to be read before the DISCARD
+            while self.state == State::Streaming {
+                self.receive(&mut connection).await?;
+            }
+            self.buffer.clear();
+            if self.state != State::Complete {
+                let discard = BoltRequest::discard(self.qid);
+                connection.send_for(self.qid, discard).await?;
+                match connection.recv_for(self.qid).await? {
+                    BoltResponse::Success(s) => self.complete(ResultSummary::new(&s, self.t_first)),
+                    BoltResponse::Failure(failure) => return Err(failure.into_error()),
+                    msg => return Err(unexpected(msg, "DISCARD")),
+                }
+            }
+        }
+        self.summary.ok_or_else(|| {
+            Error::UnexpectedMessage("the stream ended without a summary".to_owned())
+        })
+    }
+
+    fn complete(&mut self, summary: ResultSummary) {
+        if let Some((manager, sent)) = self.bookmarks.take() {
+            manager.update(&sent, summary.bookmark.clone());
+        }
+        self.summary = Some(summary);
+        self.state = State::Complete;
+    }
+
+    /// Ends the stream after an error, rows buffered from the failed batch are dropped
+    fn abort(&mut self, error: Error) -> Error {
+        record_error(&self.batch, &error);
+        self.batch = Span::none();
+        self.state = State::Complete;
+        self.buffer.clear();
+        error
+    }
 }
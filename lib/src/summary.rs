@@ -0,0 +1,288 @@
+use crate::messages::Success;
+use crate::types::*;
+use std::time::Duration;
+
+/// Summary of a query execution, returned once all records of a result have been received or
+/// discarded, see [`crate::RowStream::finish`] and [`crate::Graph::run_with_summary`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResultSummary {
+    pub counters: Counters,
+    pub query_type: Option<QueryType>,
+    /// Time until the server had the first record available
+    pub result_available_after: Option<Duration>,
+    /// Time until the server had consumed all records
+    pub result_consumed_after: Option<Duration>,
+    /// The database the query was executed against
+    pub database: Option<String>,
+    /// Only present for auto-commit queries, transactions return it on commit
+    pub bookmark: Option<String>,
+    pub notifications: Vec<Notification>,
+}
+
+/// Statistics of the changes a query made
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Counters {
+    pub nodes_created: i64,
+    pub nodes_deleted: i64,
+    pub relationships_created: i64,
+    pub relationships_deleted: i64,
+    pub properties_set: i64,
+    pub labels_added: i64,
+    pub labels_removed: i64,
+    pub indexes_added: i64,
+    pub indexes_removed: i64,
+    pub constraints_added: i64,
+    pub constraints_removed: i64,
+    pub system_updates: i64,
+    pub contains_updates: bool,
+    pub contains_system_updates: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QueryType {
+    ReadOnly,
+    ReadWrite,
+    WriteOnly,
+    SchemaWrite,
+}
+
+/// A warning or hint the server attached to a query, e.g. about a deprecated feature
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Notification {
+    pub code: String,
+    pub title: String,
+    pub description: String,
+    pub severity: String,
+    /// Only sent by Neo4j 5.x servers
+    pub category: Option<String>,
+    pub position: Option<InputPosition>,
+}
+
+/// Position of a notification within the query text
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InputPosition {
+    pub offset: i64,
+    pub line: i64,
+    pub column: i64,
+}
+
+impl ResultSummary {
+    /// Builds the summary from the final SUCCESS of a PULL or DISCARD, `t_first` is taken from
+    /// the SUCCESS of the RUN that started the result.
+    pub(crate) fn new(success: &Success, t_first: Option<i64>) -> ResultSummary {
+        let counters = success
+            .get::<BoltMap>("stats")
+            .map(|stats| Counters::new(&stats))
+            .unwrap_or_default();
+        let notifications = success
+            .get::<BoltList>("notifications")
+            .map(|list| {
+                list.into_iter()
+                    .filter_map(|n| BoltMap::try_from(n).ok())
+                    .map(|n| Notification::new(&n))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        ResultSummary {
+            counters,
+            query_type: success
+                .get::<String>("type")
+                .and_then(|t| QueryType::parse(&t)),
+            result_available_after: t_first.map(millis),
+            result_consumed_after: success.get::<i64>("t_last").map(millis),
+            database: success.get("db"),
+            bookmark: success.get("bookmark"),
+            notifications,
+        }
+    }
+}
+
+fn millis(value: i64) -> Duration {
+    Duration::from_millis(value.max(0) as u64)
+}
+
+impl Counters {
+    fn new(stats: &BoltMap) -> Counters {
+        let count = |key: &str| stats.get::<i64>(key).unwrap_or(0);
+        let mut counters = Counters {
+            nodes_created: count("nodes-created"),
+            nodes_deleted: count("nodes-deleted"),
+            relationships_created: count("relationships-created"),
+            relationships_deleted: count("relationships-deleted"),
+            properties_set: count("properties-set"),
+            labels_added: count("labels-added"),
+            labels_removed: count("labels-removed"),
+            indexes_added: count("indexes-added"),
+            indexes_removed: count("indexes-removed"),
+            constraints_added: count("constraints-added"),
+            constraints_removed: count("constraints-removed"),
+            system_updates: count("system-updates"),
+            contains_updates: false,
+            contains_system_updates: false,
+        };
+        counters.contains_updates = stats
+            .get("contains-updates")
+            .unwrap_or(counters.total_updates() > 0);
+        counters.contains_system_updates = stats
+            .get("contains-system-updates")
+            .unwrap_or(counters.system_updates > 0);
+        counters
+    }
+
+    fn total_updates(&self) -> i64 {
+        self.nodes_created
+            + self.nodes_deleted
+            + self.relationships_created
+            + self.relationships_deleted
+            + self.properties_set
+            + self.labels_added
+            + self.labels_removed
+            + self.indexes_added
+            + self.indexes_removed
+            + self.constraints_added
+            + self.constraints_removed
+    }
+}
+
+impl QueryType {
+    fn parse(value: &str) -> Option<QueryType> {
+        match value {
+            "r" => Some(QueryType::ReadOnly),
+            "rw" => Some(QueryType::ReadWrite),
+            "w" => Some(QueryType::WriteOnly),
+            "s" => Some(QueryType::SchemaWrite),
+            _ => None,
+        }
+    }
+}
+
+impl Notification {
+    fn new(notification: &BoltMap) -> Notification {
+        let position = notification
+            .get::<BoltMap>("position")
+            .map(|position| InputPosition {
+                offset: position.get("offset").unwrap_or(0),
+                line: position.get("line").unwrap_or(0),
+                column: position.get("column").unwrap_or(0),
+            });
+        Notification {
+            code: notification.get("code").unwrap_or_default(),
+            title: notification.get("title").unwrap_or_default(),
+            description: notification.get("description").unwrap_or_default(),
+            severity: notification.get("severity").unwrap_or_default(),
+            category: notification.get("category"),
+            position,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::version::Version;
+    use bytes::*;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    fn success(metadata: Vec<(&str, BoltType)>) -> Success {
+        let metadata: BoltMap = metadata.into_iter().map(|(k, v)| (k.into(), v)).collect();
+        let mut bytes = BytesMut::from(&[0xB1, 0x70][..]);
+        bytes.put(metadata.into_bytes(Version::V4_4).unwrap());
+        Success::parse(Version::V4_4, Rc::new(RefCell::new(bytes.freeze()))).unwrap()
+    }
+
+    #[test]
+    fn should_build_summary_from_success() {
+        let stats: BoltMap = vec![
+            ("nodes-created".into(), 2.into()),
+            ("properties-set".into(), 3.into()),
+            ("indexes-added".into(), 1.into()),
+        ]
+        .into_iter()
+        .collect();
+        let success = success(vec![
+            ("stats", BoltType::Map(stats)),
+            ("type", "w".into()),
+            ("t_last", 5.into()),
+            ("db", "neo4j".into()),
+            ("bookmark", "FB:kcwQ".into()),
+        ]);
+
+        let summary = ResultSummary::new(&success, Some(3));
+
+        assert_eq!(summary.counters.nodes_created, 2);
+        assert_eq!(summary.counters.properties_set, 3);
+        assert_eq!(summary.counters.indexes_added, 1);
+        assert_eq!(summary.counters.relationships_deleted, 0);
+        assert!(summary.counters.contains_updates);
+        assert!(!summary.counters.contains_system_updates);
+        assert_eq!(summary.query_type, Some(QueryType::WriteOnly));
+        assert_eq!(
+            summary.result_available_after,
+            Some(Duration::from_millis(3))
+        );
+        assert_eq!(
+            summary.result_consumed_after,
+            Some(Duration::from_millis(5))
+        );
+        assert_eq!(summary.database.as_deref(), Some("neo4j"));
+        assert_eq!(summary.bookmark.as_deref(), Some("FB:kcwQ"));
+        assert!(summary.notifications.is_empty());
+    }
+
+    #[test]
+    fn should_build_summary_without_stats() {
+        let summary = ResultSummary::new(&success(vec![("type", "r".into())]), None);
+
+        assert_eq!(summary.counters, Counters::default());
+        assert_eq!(summary.query_type, Some(QueryType::ReadOnly));
+        assert_eq!(summary.result_available_after, None);
+    }
+
+    #[test]
+    fn should_parse_notifications() {
+        let position: BoltMap = vec![
+            ("offset".into(), 7.into()),
+            ("line".into(), 1.into()),
+            ("column".into(), 8.into()),
+        ]
+        .into_iter()
+        .collect();
+        let notification: BoltMap = vec![
+            (
+                "code".into(),
+                "Neo.ClientNotification.Statement.CartesianProduct".into(),
+            ),
+            ("title".into(), "cartesian product".into()),
+            ("description".into(), "slow".into()),
+            ("severity".into(), "WARNING".into()),
+            ("position".into(), BoltType::Map(position)),
+        ]
+        .into_iter()
+        .collect();
+        let success = success(vec![(
+            "notifications",
+            BoltType::List(vec![BoltType::Map(notification)].into()),
+        )]);
+
+        let summary = ResultSummary::new(&success, None);
+
+        assert_eq!(summary.notifications.len(), 1);
+        let notification = &summary.notifications[0];
+        assert_eq!(
+            notification.code,
+            "Neo.ClientNotification.Statement.CartesianProduct"
+        );
+        assert_eq!(notification.severity, "WARNING");
+        assert_eq!(notification.category, None);
+        assert_eq!(
+            notification.position,
+            Some(InputPosition {
+                offset: 7,
+                line: 1,
+                column: 8
+            })
+        );
+    }
+}
@@ -0,0 +1,267 @@
+use crate::errors::Error;
+use crate::pool::{server_address, ManagedConnection};
+use crate::uri::Uri;
+use std::future::Future;
+
+#[cfg(feature = "tracing")]
+pub(crate) use tracing::{field::Empty, Instrument, Span};
+
+/// A span of the `tracing` feature named after what it covers, like a bolt message. Fields follow
+/// the OpenTelemetry conventions for database clients, the fields every span has are added and
+/// filled in by [`record_error`]. Without the feature the span does nothing.
+#[cfg(feature = "tracing")]
+macro_rules! span {
+    ($name:literal $(, $($key:ident).+ = $value:expr)* $(,)?) => {
+        tracing::info_span!(
+            $name,
+            $($($key).+ = $value,)*
+            db.system = "neo4j",
+            otel.status_code = tracing::field::Empty,
+            error.type = tracing::field::Empty,
+            db.response.status_code = tracing::field::Empty,
+        )
+    };
+}
+
+#[cfg(not(feature = "tracing"))]
+macro_rules! span {
+    ($name:literal $(, $($key:ident).+ = $value:expr)* $(,)?) => {{
+        $(let _ = &$value;)*
+        $crate::trace::Span::none()
+    }};
+}
+
+pub(crate) use span;
+
+/// Stands in for [`tracing::Span`] without the `tracing` feature
+#[cfg(not(feature = "tracing"))]
+#[derive(Debug, Clone)]
+pub(crate) struct Span;
+
+#[cfg(not(feature = "tracing"))]
+impl Span {
+    pub(crate) fn none() -> Span {
+        Span
+    }
+
+    pub(crate) fn record<V>(&self, _field: &str, _value: V) -> &Self {
+        self
+    }
+}
+
+/// Stands in for [`tracing::field::Empty`] without the `tracing` feature
+#[cfg(not(feature = "tracing"))]
+pub(crate) struct Empty;
+
+/// Stands in for [`tracing::Instrument`] without the `tracing` feature
+#[cfg(not(feature = "tracing"))]
+pub(crate) trait Instrument: Sized {
+    fn instrument(self, _span: Span) -> Self {
+        self
+    }
+}
+
+#[cfg(not(feature = "tracing"))]
+impl<T> Instrument for T {}
+
+/// Runs `future` in `span` and records its error, if any
+pub(crate) async fn traced<T, F>(span: Span, future: F) -> Result<T, Error>
+where
+    F: Future<Output = Result<T, Error>>,
+{
+    let result = future.instrument(span.clone()).await;
+    if let Err(e) = &result {
+        record_error(&span, e);
+    }
+    result
+}
+
+/// Marks `span` as failed, with the code of the error if the server reported it and the kind
+/// of the error otherwise
+pub(crate) fn record_error(span: &Span, error: &Error) {
+    span.record("otel.status_code", "ERROR");
+    match error {
+        Error::Neo4jError(e) => {
+            span.record("error.type", e.code.as_str());
+            span.record("db.response.status_code", e.code.as_str());
+        }
+        Error::BatchFailed { error, .. } => record_error(span, error),
+        error => {
+            span.record("error.type", error_kind(error).as_str());
+        }
+    }
+}
+
+/// The name of the variant of `error`, e.g. `ConnectionError`
+fn error_kind(error: &Error) -> String {
+    format!("{:?}", error)
+        .split(|c: char| !c.is_alphanumeric())
+        .next()
+        .unwrap_or_default()
+        .to_owned()
+}
+
+/// Records `server.address` and `server.port` of the server `connection` is connected to
+pub(crate) fn record_server(span: &Span, connection: &ManagedConnection) {
+    if let Some((host, port)) = server_address(connection)
+        .as_deref()
+        .and_then(Uri::split_host_port)
+    {
+        span.record("server.address", host.as_str());
+        span.record("server.port", port);
+    }
+}
+
+/// The database for `db.namespace`, `None` for the default database of the server
+pub(crate) fn namespace(db: &str) -> Option<&str> {
+    if db.is_empty() {
+        None
+    } else {
+        Some(db)
+    }
+}
+
+#[cfg(all(test, feature = "tracing"))]
+mod tests {
+    use super::*;
+    use crate::connection::tests::{failure, ignored, record, stub_server, success, success_with};
+    use crate::types::{BoltList, BoltMap};
+    use crate::{config, query, Graph};
+    use std::collections::HashMap;
+    use std::fmt::Debug;
+    use std::sync::{Arc, Mutex};
+    use tracing::field::{Field, Visit};
+    use tracing::span::{Attributes, Id, Record};
+    use tracing::{Event, Metadata};
+
+    type Recorded = Vec<(&'static str, HashMap<String, String>)>;
+
+    /// The names and fields of the spans created while it is the default subscriber
+    #[derive(Default, Clone)]
+    struct Spans(Arc<Mutex<Recorded>>);
+
+    impl Spans {
+        fn named(&self, name: &str) -> Vec<HashMap<String, String>> {
+            let spans = self.0.lock().unwrap();
+            spans
+                .iter()
+                .filter(|span| span.0 == name)
+                .map(|span| span.1.clone())
+                .collect()
+        }
+    }
+
+    struct Fields<'a>(&'a mut HashMap<String, String>);
+
+    impl Visit for Fields<'_> {
+        fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+            self.0.insert(
+                field.name().to_owned(),
+                format!("{:?}", value).replace('"', ""),
+            );
+        }
+    }
+
+    impl tracing::Subscriber for Spans {
+        fn enabled(&self, _: &Metadata<'_>) -> bool {
+            true
+        }
+
+        fn new_span(&self, attributes: &Attributes<'_>) -> Id {
+            let mut spans = self.0.lock().unwrap();
+            let mut fields = HashMap::new();
+            attributes.record(&mut Fields(&mut fields));
+            spans.push((attributes.metadata().name(), fields));
+            Id::from_u64(spans.len() as u64)
+        }
+
+        fn record(&self, span: &Id, values: &Record<'_>) {
+            let mut spans = self.0.lock().unwrap();
+            let fields = &mut spans[span.into_u64() as usize - 1].1;
+            values.record(&mut Fields(fields));
+        }
+
+        fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+        fn event(&self, _: &Event<'_>) {}
+
+        fn enter(&self, _: &Id) {}
+
+        fn exit(&self, _: &Id) {}
+    }
+
+    fn row(value: i64) -> bytes::Bytes {
+        let mut values = BoltList::new();
+        values.push(value.into());
+        record(values)
+    }
+
+    #[tokio::test]
+    async fn should_trace_connections_and_queries() {
+        let mut run = BoltMap::default();
+        run.put("fields".into(), vec!["n"].into());
+        run.put("qid".into(), 3.into());
+        let mut has_more = BoltMap::default();
+        has_more.put("has_more".into(), true.into());
+        let uri = stub_server(vec![
+            vec![success()],
+            vec![],
+            vec![success_with(run), row(1), row(2), success_with(has_more)],
+            vec![row(3), success()],
+            vec![],
+            vec![failure("Neo.ClientError.Statement.SyntaxError"), ignored()],
+        ])
+        .await;
+        let spans = Spans::default();
+        let _guard = tracing::subscriber::set_default(spans.clone());
+        let config = config()
+            .uri(&uri)
+            .user("user")
+            .password("pass")
+            .db("movies")
+            .fetch_size(2)
+            .build()
+            .unwrap();
+        let graph = Graph::connect(config).await.unwrap();
+
+        let mut result = graph
+            .execute(query("UNWIND [1, 2, 3] AS n RETURN n"))
+            .await
+            .unwrap();
+        while result.next().await.unwrap().is_some() {}
+        drop(result);
+        assert!(graph.run(query("RETRUN 1")).await.is_err());
+
+        let (_, port) = Uri::split_host_port(&uri).unwrap();
+        let connect = &spans.named("connect")[0];
+        assert_eq!(connect["server.port"], port.to_string());
+        assert_eq!(spans.named("handshake")[0]["neo4j.bolt.version"], "4.4");
+        assert_eq!(spans.named("HELLO").len(), 1);
+        let acquire = spans.named("acquire connection");
+        assert_eq!(acquire[0]["db.namespace"], "movies");
+        assert_eq!(acquire[0]["server.port"], port.to_string());
+        let runs = spans.named("RUN");
+        assert_eq!(runs[0]["db.query.text"], "UNWIND [1, 2, 3] AS n RETURN n");
+        assert_eq!(runs[0]["neo4j.qid"], "3");
+        assert_eq!(runs[0]["db.system"], "neo4j");
+        assert_eq!(runs[1]["otel.status_code"], "ERROR");
+        assert_eq!(
+            runs[1]["db.response.status_code"],
+            "Neo.ClientError.Statement.SyntaxError"
+        );
+        let pulls = spans.named("PULL");
+        assert_eq!(pulls.len(), 2);
+        assert_eq!(pulls[0]["neo4j.qid"], "3");
+        assert_eq!(pulls[0]["db.response.returned_rows"], "2");
+        assert_eq!(pulls[1]["db.response.returned_rows"], "1");
+    }
+
+    #[test]
+    fn should_name_the_kind_of_errors() {
+        assert_eq!(error_kind(&Error::ConnectionError), "ConnectionError");
+        assert_eq!(
+            error_kind(&Error::ConnectTimeout("localhost:7687".into())),
+            "ConnectTimeout"
+        );
+    }
+}
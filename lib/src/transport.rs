@@ -0,0 +1,265 @@
+use crate::errors::*;
+use crate::uri::{Encryption, Uri};
+use std::io;
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio::net::TcpStream;
+
+#[cfg(feature = "rustls")]
+use std::sync::Arc;
+
+/// Certificates used for encrypted connections, see [`crate::ConfigBuilder::ca_certificate`]
+/// and [`crate::ConfigBuilder::client_certificate`]
+#[derive(Debug, Clone, Default)]
+pub struct TlsConfig {
+    pub(crate) ca_certificate: Option<PathBuf>,
+    pub(crate) client_certificate: Option<(PathBuf, PathBuf)>,
+}
+
+/// The byte stream bolt messages are exchanged over
+#[derive(Debug)]
+pub enum Transport {
+    Plain(TcpStream),
+    #[cfg(feature = "rustls")]
+    Tls(Box<tokio_rustls::client::TlsStream<TcpStream>>),
+}
+
+/// Opens plain or encrypted streams to servers, built once per pool so certificates are only
+/// loaded once
+#[derive(Clone)]
+pub enum Connector {
+    Plain,
+    #[cfg(feature = "rustls")]
+    Tls(Arc<rustls::ClientConfig>),
+}
+
+impl Connector {
+    pub fn new(uri: &Uri, tls: &TlsConfig) -> Result<Connector> {
+        match uri.encryption() {
+            Encryption::None => Ok(Connector::Plain),
+            #[cfg(feature = "rustls")]
+            encryption => Ok(Connector::Tls(Arc::new(rustls_config::build(
+                encryption, tls,
+            )?))),
+            #[cfg(not(feature = "rustls"))]
+            encryption => {
+                let _ = tls;
+                let scheme = match (uri.routing, encryption) {
+                    (false, Encryption::SelfSigned) => "bolt+ssc",
+                    (true, Encryption::SelfSigned) => "neo4j+ssc",
+                    (false, _) => "bolt+s",
+                    (true, _) => "neo4j+s",
+                };
+                Err(Error::UnsupportedScheme(format!(
+                    "{}, encrypted connections require the `rustls` feature",
+                    scheme
+                )))
+            }
+        }
+    }
+
+    /// Opens a TCP connection and runs the TLS handshake for encrypted uris
+    pub async fn connect(&self, host: &str, port: u16) -> Result<Transport> {
+        let stream = TcpStream::connect((host, port)).await?;
+        match self {
+            Connector::Plain => Ok(Transport::Plain(stream)),
+            #[cfg(feature = "rustls")]
+            Connector::Tls(config) => {
+                let server_name = rustls::pki_types::ServerName::try_from(host.to_owned())
+                    .map_err(|e| Error::TlsError(e.to_string()))?;
+                let stream = tokio_rustls::TlsConnector::from(config.clone())
+                    .connect(server_name, stream)
+                    .await
+                    .map_err(|e| Error::TlsError(e.to_string()))?;
+                Ok(Transport::Tls(Box::new(stream)))
+            }
+        }
+    }
+}
+
+impl std::fmt::Debug for Connector {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Connector::Plain => write!(f, "Plain"),
+            #[cfg(feature = "rustls")]
+            Connector::Tls(_) => write!(f, "Tls"),
+        }
+    }
+}
+
+#[cfg(feature = "rustls")]
+mod rustls_config {
+    use super::TlsConfig;
+    use crate::errors::*;
+    use crate::uri::Encryption;
+    use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+    use rustls::crypto::CryptoProvider;
+    use rustls::pki_types::{CertificateDer, PrivateKeyDer, ServerName, UnixTime};
+    use rustls::{ClientConfig, DigitallySignedStruct, RootCertStore, SignatureScheme};
+    use std::path::Path;
+    use std::sync::Arc;
+
+    pub fn build(encryption: Encryption, tls: &TlsConfig) -> Result<ClientConfig> {
+        let provider = Arc::new(rustls::crypto::ring::default_provider());
+        let builder = ClientConfig::builder_with_provider(provider.clone())
+            .with_safe_default_protocol_versions()
+            .map_err(tls_error)?;
+        let builder = match encryption {
+            Encryption::SelfSigned => builder
+                .dangerous()
+                .with_custom_certificate_verifier(Arc::new(AcceptAnyCertificate(provider))),
+            _ => {
+                let mut roots = RootCertStore::empty();
+                roots.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned());
+                if let Some(path) = &tls.ca_certificate {
+                    for certificate in load_certificates(path)? {
+                        roots.add(certificate).map_err(tls_error)?;
+                    }
+                }
+                builder.with_root_certificates(roots)
+            }
+        };
+        match &tls.client_certificate {
+            Some((certificate, key)) => builder
+                .with_client_auth_cert(load_certificates(certificate)?, load_key(key)?)
+                .map_err(tls_error),
+            None => Ok(builder.with_no_client_auth()),
+        }
+    }
+
+    fn tls_error(e: rustls::Error) -> Error {
+        Error::TlsError(e.to_string())
+    }
+
+    fn load_certificates(path: &Path) -> Result<Vec<CertificateDer<'static>>> {
+        let mut reader = std::io::BufReader::new(std::fs::File::open(path)?);
+        let certificates =
+            rustls_pemfile::certs(&mut reader).collect::<std::io::Result<Vec<_>>>()?;
+        if certificates.is_empty() {
+            return Err(Error::TlsError(format!(
+                "no certificates found in {}",
+                path.display()
+            )));
+        }
+        Ok(certificates)
+    }
+
+    fn load_key(path: &Path) -> Result<PrivateKeyDer<'static>> {
+        let mut reader = std::io::BufReader::new(std::fs::File::open(path)?);
+        rustls_pemfile::private_key(&mut reader)?
+            .ok_or_else(|| Error::TlsError(format!("no private key found in {}", path.display())))
+    }
+
+    /// Skips certificate validation for `+ssc` schemes, handshake signatures are still checked
+    #[derive(Debug)]
+    struct AcceptAnyCertificate(Arc<CryptoProvider>);
+
+    impl ServerCertVerifier for AcceptAnyCertificate {
+        fn verify_server_cert(
+            &self,
+            _end_entity: &CertificateDer<'_>,
+            _intermediates: &[CertificateDer<'_>],
+            _server_name: &ServerName<'_>,
+            _ocsp_response: &[u8],
+            _now: UnixTime,
+        ) -> std::result::Result<ServerCertVerified, rustls::Error> {
+            Ok(ServerCertVerified::assertion())
+        }
+
+        fn verify_tls12_signature(
+            &self,
+            message: &[u8],
+            cert: &CertificateDer<'_>,
+            dss: &DigitallySignedStruct,
+        ) -> std::result::Result<HandshakeSignatureValid, rustls::Error> {
+            rustls::crypto::verify_tls12_signature(
+                message,
+                cert,
+                dss,
+                &self.0.signature_verification_algorithms,
+            )
+        }
+
+        fn verify_tls13_signature(
+            &self,
+            message: &[u8],
+            cert: &CertificateDer<'_>,
+            dss: &DigitallySignedStruct,
+        ) -> std::result::Result<HandshakeSignatureValid, rustls::Error> {
+            rustls::crypto::verify_tls13_signature(
+                message,
+                cert,
+                dss,
+                &self.0.signature_verification_algorithms,
+            )
+        }
+
+        fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+            self.0.signature_verification_algorithms.supported_schemes()
+        }
+    }
+}
+
+impl AsyncRead for Transport {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            Transport::Plain(stream) => Pin::new(stream).poll_read(cx, buf),
+            #[cfg(feature = "rustls")]
+            Transport::Tls(stream) => Pin::new(stream).poll_read(cx, buf),
+        }
+    }
+}
+
+impl AsyncWrite for Transport {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        match self.get_mut() {
+            Transport::Plain(stream) => Pin::new(stream).poll_write(cx, buf),
+            #[cfg(feature = "rustls")]
+            Transport::Tls(stream) => Pin::new(stream).poll_write(cx, buf),
+        }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            Transport::Plain(stream) => Pin::new(stream).poll_flush(cx),
+            #[cfg(feature = "rustls")]
+            Transport::Tls(stream) => Pin::new(stream).poll_flush(cx),
+        }
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            Transport::Plain(stream) => Pin::new(stream).poll_shutdown(cx),
+            #[cfg(feature = "rustls")]
+            Transport::Tls(stream) => Pin::new(stream).poll_shutdown(cx),
+        }
+    }
+}
+
+#[cfg(all(test, not(feature = "rustls")))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_reject_encrypted_schemes_without_rustls() {
+        for scheme in ["bolt+s", "bolt+ssc", "neo4j+s", "neo4j+ssc"] {
+            let uri = Uri::parse(&format!("{}://localhost:7687", scheme)).unwrap();
+            match Connector::new(&uri, &TlsConfig::default()) {
+                Err(Error::UnsupportedScheme(message)) => assert!(message.starts_with(scheme)),
+                _ => panic!("expected an unsupported scheme error for {}", scheme),
+            }
+        }
+        let uri = Uri::parse("bolt://localhost:7687").unwrap();
+        assert!(Connector::new(&uri, &TlsConfig::default()).is_ok());
+    }
+}
@@ -1,34 +1,325 @@
 use crate::config::Config;
+use crate::connection::ConnectionState;
 use crate::errors::*;
 use crate::messages::*;
 use crate::pool::*;
 use crate::query::*;
+use crate::routing::AccessMode;
 use crate::stream::*;
+use crate::summary::ResultSummary;
+use crate::trace::{namespace, record_server, span, traced, Empty};
+use crate::types::{BoltMap, BoltType};
+use log::warn;
+use std::mem;
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::Mutex;
 
+/// Options of a transaction, accepted by [`crate::Graph::start_txn_with`] and, for auto-commit
+/// queries, by [`crate::Graph::run_with`] and [`crate::Graph::execute_with`].
+///
+/// ```
+/// use neo4rs::*;
+/// use std::time::Duration;
+///
+/// let config = TxnConfig::new()
+///     .timeout(Duration::from_secs(5))
+///     .metadata("app", "billing")
+///     .mode(AccessMode::Read)
+///     .db("movies");
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct TxnConfig {
+    mode: AccessMode,
+    db: Option<String>,
+    bookmarks: Vec<String>,
+    timeout: Option<Duration>,
+    metadata: BoltMap,
+    imp_user: Option<String>,
+    error: Option<Error>,
+}
+
+impl TxnConfig {
+    pub fn new() -> Self {
+        TxnConfig::default()
+    }
+
+    /// The time after which the server terminates the transaction, instead of the timeout
+    /// configured on the server
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Attaches metadata to the transaction, which is shown by `SHOW TRANSACTIONS` and in the
+    /// query log. Values which fail to serialize make the transaction fail when it starts.
+    pub fn metadata<T: IntoParam>(mut self, key: &str, value: T) -> Self {
+        match value.into_param() {
+            Ok(value) => self.metadata.put(key.into(), value),
+            Err(e) => {
+                if self.error.is_none() {
+                    self.error = Some(e);
+                }
+            }
+        }
+        self
+    }
+
+    /// Whether the transaction is routed to the readers or the leader of a cluster
+    pub fn mode(mut self, mode: AccessMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// The database the transaction runs against, instead of [`crate::ConfigBuilder::db`]
+    pub fn db(mut self, db: &str) -> Self {
+        self.db = Some(db.to_owned());
+        self
+    }
+
+    /// The user the transaction is executed as, instead of
+    /// [`crate::SessionConfig::impersonated_user`]. Requires Neo4j 4.4 or later and the
+    /// privilege to impersonate that user.
+    pub fn impersonate(mut self, user: &str) -> Self {
+        self.imp_user = Some(user.to_owned());
+        self
+    }
+
+    /// Bookmarks the server has to catch up with before the transaction starts
+    pub fn bookmarks(mut self, bookmarks: Vec<String>) -> Self {
+        self.add_bookmarks(bookmarks);
+        self
+    }
+
+    pub(crate) fn add_bookmarks(&mut self, bookmarks: Vec<String>) {
+        for bookmark in bookmarks {
+            if !self.bookmarks.contains(&bookmark) {
+                self.bookmarks.push(bookmark);
+            }
+        }
+    }
+
+    pub(crate) fn access_mode(&self) -> AccessMode {
+        self.mode
+    }
+
+    pub(crate) fn sent_bookmarks(&self) -> &[String] {
+        &self.bookmarks
+    }
+
+    pub(crate) fn take_bookmarks(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.bookmarks)
+    }
+
+    pub(crate) fn take_error(&mut self) -> Option<Error> {
+        self.error.take()
+    }
+
+    /// The graph config with the database and impersonated user of the transaction, if it has
+    /// them
+    pub(crate) fn apply(&self, config: &Config) -> Config {
+        let mut config = config.clone();
+        if let Some(db) = &self.db {
+            config.db = db.clone();
+        }
+        if let Some(imp_user) = &self.imp_user {
+            config.imp_user = Some(imp_user.clone());
+        }
+        config
+    }
+
+    /// The entries of the BEGIN or auto-commit RUN extra map, except the bookmarks
+    pub(crate) fn extra(&self, config: &Config) -> BoltMap {
+        let mut extra = BoltMap::default();
+        self.mode.put_into(&mut extra);
+        if !config.db.is_empty() {
+            extra.put("db".into(), config.db.as_str().into());
+        }
+        if let Some(imp_user) = &config.imp_user {
+            extra.put("imp_user".into(), imp_user.as_str().into());
+        }
+        if let Some(timeout) = self.timeout {
+            extra.put("tx_timeout".into(), (timeout.as_millis() as i64).into());
+        }
+        if !self.metadata.value.is_empty() {
+            extra.put("tx_metadata".into(), BoltType::Map(self.metadata.clone()));
+        }
+        extra
+    }
+}
+
+/// The state of a [`Txn`], queries can only run while it is active
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TxnState {
+    Active,
+    Committed,
+    RolledBack,
+    /// A query or the commit failed, the transaction can only be rolled back
+    Failed,
+}
+
 /// A handle which is used to control a transaction, created as a result of [`crate::Graph::start_txn`]
 ///
 /// When a transation is started, a dedicated connection is resered and moved into the handle which
-/// will be released to the connection pool when the [`Txn`] handle is dropped.
+/// will be released to the connection pool when the [`Txn`] handle is dropped. A transaction which
+/// is dropped while it is still active or failed is rolled back before the connection is released.
 pub struct Txn {
     config: Config,
+    shared: Arc<Shared>,
+    bookmarks: Vec<String>,
+}
+
+/// What all handles of a transaction share, the transaction is rolled back when the last one
+/// is dropped without committing or rolling back
+struct Shared {
+    state: std::sync::Mutex<TxnState>,
     connection: Arc<Mutex<ManagedConnection>>,
+    db: String,
+}
+
+impl Drop for Shared {
+    fn drop(&mut self) {
+        let state = *self.state.lock().unwrap();
+        if state != TxnState::Active && state != TxnState::Failed {
+            return;
+        }
+        if state == TxnState::Active {
+            warn!("transaction dropped without commit or rollback, rolling it back");
+        }
+        // Without a runtime the RESET sent when the pool recycles the connection rolls back
+        if let Ok(runtime) = tokio::runtime::Handle::try_current() {
+            let connection = self.connection.clone();
+            let db = mem::take(&mut self.db);
+            runtime.spawn(async move {
+                let mut connection = connection.lock().await;
+                if connection.is_defunct() {
+                    return;
+                }
+                if let Err(e) = roll_back(&mut connection, &db).await {
+                    warn!("could not roll back the dropped transaction: {}", e);
+                }
+            });
+        }
+    }
+}
+
+/// Rolls back with ROLLBACK, or with RESET when a query failed or a stream is still open
+async fn roll_back(connection: &mut ManagedConnection, db: &str) -> Result<()> {
+    let span = span!(
+        "ROLLBACK",
+        db.operation.name = "ROLLBACK",
+        db.namespace = namespace(db),
+        server.address = Empty,
+        server.port = Empty,
+    );
+    record_server(&span, connection);
+    let result = async {
+        connection.catch_up().await?;
+        if connection.state() != ConnectionState::TxReady {
+            return connection.reset().await;
+        }
+        let rollback = BoltRequest::rollback();
+        match connection.send_recv(rollback).await? {
+            BoltResponse::Success(_) => Ok(()),
+            BoltResponse::Failure(failure) => Err(failure.into_error()),
+            msg => Err(unexpected(msg, "ROLLBACK")),
+        }
+    };
+    traced(span, result).await
 }
 
 impl Txn {
-    pub(crate) async fn new(config: Config, mut connection: ManagedConnection) -> Result<Self> {
-        let begin = BoltRequest::begin();
-        match connection.send_recv(begin).await? {
-            BoltResponse::Success(_) => Ok(Txn {
-                config,
+    pub(crate) async fn new(
+        config: Config,
+        mut connection: ManagedConnection,
+        mut txn_config: TxnConfig,
+    ) -> Result<Self> {
+        let mut extra = txn_config.extra(&config);
+        let bookmarks = txn_config.take_bookmarks();
+        if !bookmarks.is_empty() {
+            extra.put("bookmarks".into(), bookmarks.clone().into());
+        }
+        let span = span!(
+            "BEGIN",
+            db.operation.name = "BEGIN",
+            db.namespace = namespace(&config.db),
+            server.address = Empty,
+            server.port = Empty,
+        );
+        record_server(&span, &connection);
+        // the response to BEGIN is read with the response to the first query, a failure to
+        // begin is reported by that query
+        let begin = connection.send_deferred(BoltRequest::begin(extra));
+        traced(span, begin).await?;
+        let db = config.db.clone();
+        Ok(Txn {
+            config,
+            shared: Arc::new(Shared {
+                state: std::sync::Mutex::new(TxnState::Active),
                 connection: Arc::new(Mutex::new(connection)),
+                db,
             }),
-            msg => Err(unexpected(msg, "BEGIN")),
+            bookmarks,
+        })
+    }
+
+    /// Another handle to the same transaction, handed to transaction functions
+    pub(crate) fn share(&self) -> Txn {
+        Txn {
+            config: self.config.clone(),
+            shared: self.shared.clone(),
+            bookmarks: self.bookmarks.clone(),
         }
     }
 
-    /// Runs multiple queries one after the other in the same connection
+    pub(crate) async fn server_address(&self) -> Option<String> {
+        server_address(&*self.shared.connection.lock().await)
+    }
+
+    pub fn state(&self) -> TxnState {
+        *self.shared.state.lock().unwrap()
+    }
+
+    fn set_state(&self, state: TxnState) {
+        *self.shared.state.lock().unwrap() = state;
+    }
+
+    /// Fails unless the transaction is active, a transaction whose connection failed, e.g.
+    /// because a stream of it failed, is marked as failed
+    async fn ensure_active(&self) -> Result<()> {
+        if self.state() == TxnState::Active {
+            let connection = self.shared.connection.lock().await;
+            if connection.is_failed() || connection.is_defunct() {
+                self.set_state(TxnState::Failed);
+            }
+        }
+        match self.state() {
+            TxnState::Active => Ok(()),
+            TxnState::Committed => Err(Error::InvalidTransactionState(
+                "the transaction was already committed".into(),
+            )),
+            TxnState::RolledBack => Err(Error::InvalidTransactionState(
+                "the transaction was already rolled back".into(),
+            )),
+            TxnState::Failed => Err(Error::InvalidTransactionState(
+                "an earlier query of the transaction failed, it can only be rolled back".into(),
+            )),
+        }
+    }
+
+    /// Marks the transaction as failed if `result` failed on the server or the connection
+    async fn track<T>(&self, result: Result<T>) -> Result<T> {
+        if result.is_err() {
+            let connection = self.shared.connection.lock().await;
+            if connection.is_failed() || connection.is_defunct() {
+                self.set_state(TxnState::Failed);
+            }
+        }
+        result
+    }
+
+    /// Runs multiple queries one after the other in the same connection, see
+    /// [`Txn::run_batch`] to send them in one round trip
     pub async fn run_queries(&self, queries: Vec<Query>) -> Result<()> {
         for query in queries.into_iter() {
             self.run(query).await?;
@@ -36,31 +327,484 @@ impl Txn {
         Ok(())
     }
 
+    /// Sends all queries before waiting for the server and returns the [`ResultSummary`] of each.
+    ///
+    /// When a query fails the server ignores the ones after it, the error is an
+    /// [`Error::BatchFailed`] with the index of the failed query and the transaction can only be
+    /// rolled back.
+    pub async fn run_batch(&self, queries: Vec<Query>) -> Result<Vec<ResultSummary>> {
+        self.ensure_active().await?;
+        let connection = self.shared.connection.clone();
+        let (summaries, failure) = Query::run_batch(queries, &self.config, connection).await;
+        let result = match failure {
+            Some(e) => Err(e),
+            None => Ok(summaries),
+        };
+        self.track(result).await
+    }
+
     /// Runs a single query and discards the stream.
     pub async fn run(&self, q: Query) -> Result<()> {
-        q.run(&self.config, self.connection.clone()).await
+        self.run_with_summary(q).await.map(|_| ())
+    }
+
+    /// Runs a single query, discards the stream and returns its [`ResultSummary`].
+    pub async fn run_with_summary(&self, q: Query) -> Result<ResultSummary> {
+        self.ensure_active().await?;
+        let result = q.run(&self.config, self.shared.connection.clone()).await;
+        self.track(result).await
     }
 
     /// Executes a query and returns a [`RowStream`]
     pub async fn execute(&self, q: Query) -> Result<RowStream> {
-        q.execute(&self.config, self.connection.clone()).await
+        self.ensure_active().await?;
+        let result = q
+            .execute(&self.config, self.shared.connection.clone())
+            .await;
+        self.track(result).await
     }
 
     /// Commits the transaction in progress
     pub async fn commit(self) -> Result<()> {
-        let commit = BoltRequest::commit();
-        match self.connection.lock().await.send_recv(commit).await? {
-            BoltResponse::Success(_) => Ok(()),
-            msg => Err(unexpected(msg, "COMMIT")),
-        }
+        self.commit_with_bookmark().await.map(|_| ())
+    }
+
+    /// Commits the transaction in progress and returns its bookmark, which can be passed to
+    /// [`crate::Graph::start_txn_with_bookmarks`] or [`Query::bookmarks`] so that later work sees
+    /// the changes of this transaction. The bookmark manager of the graph, if any, is updated.
+    pub async fn commit_with_bookmark(self) -> Result<Option<String>> {
+        self.ensure_active().await?;
+        let span = span!(
+            "COMMIT",
+            db.operation.name = "COMMIT",
+            db.namespace = namespace(&self.config.db),
+            server.address = Empty,
+            server.port = Empty,
+        );
+        let result = async {
+            let commit = BoltRequest::commit();
+            let response = {
+                let mut connection = self.shared.connection.lock().await;
+                record_server(&span, &connection);
+                connection.send_recv(commit).await
+            };
+            match self.track(response).await? {
+                BoltResponse::Success(success) => {
+                    self.set_state(TxnState::Committed);
+                    let bookmark: Option<String> = success.get("bookmark");
+                    if let Some(manager) = &self.config.bookmark_manager {
+                        manager.update(&self.bookmarks, bookmark.clone());
+                    }
+                    Ok(bookmark)
+                }
+                BoltResponse::Failure(failure) => {
+                    self.set_state(TxnState::Failed);
+                    Err(failure.into_error())
+                }
+                msg => Err(unexpected(msg, "COMMIT")),
+            }
+        };
+        traced(span.clone(), result).await
     }
 
     /// rollback/abort the current transaction
+    ///
+    /// If a query within the transaction failed, the connection is reset instead, which rolls
+    /// back the transaction on the server.
     pub async fn rollback(self) -> Result<()> {
-        let rollback = BoltRequest::rollback();
-        match self.connection.lock().await.send_recv(rollback).await? {
-            BoltResponse::Success(_) => Ok(()),
-            msg => Err(unexpected(msg, "ROLLBACK")),
+        match self.state() {
+            TxnState::Active | TxnState::Failed => {}
+            _ => return self.ensure_active().await,
+        }
+        let result = roll_back(&mut *self.shared.connection.lock().await, &self.config.db).await;
+        match result {
+            Ok(()) => self.set_state(TxnState::RolledBack),
+            Err(_) => self.set_state(TxnState::Failed),
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::connection::tests::{
+        failure, ignored, record, recording_stub_server, request_bytes, stub_server, success,
+        success_with, Requests,
+    };
+    use crate::types::BoltList;
+    use crate::{config, query, Graph};
+    use bytes::Bytes;
+    use futures::FutureExt;
+
+    fn contains(request: &Bytes, value: &[u8]) -> bool {
+        request.windows(value.len()).any(|window| window == value)
+    }
+
+    async fn connect(uri: &str) -> Graph {
+        let config = config()
+            .uri(uri)
+            .user("user")
+            .password("pass")
+            .db("neo4j")
+            .max_connections(1)
+            .build()
+            .unwrap();
+        Graph::connect(config).await.unwrap()
+    }
+
+    #[test]
+    fn should_build_the_extra_map() {
+        let config = config()
+            .uri("localhost:7687")
+            .user("user")
+            .password("pass")
+            .build()
+            .unwrap();
+        let txn_config = TxnConfig::new()
+            .timeout(Duration::from_millis(2500))
+            .metadata("app", "billing")
+            .mode(AccessMode::Read)
+            .db("movies")
+            .impersonate("alice");
+
+        let extra = txn_config.extra(&txn_config.apply(&config));
+
+        assert_eq!(extra.get::<i64>("tx_timeout").unwrap(), 2500);
+        assert_eq!(extra.get::<String>("mode").unwrap(), "r");
+        assert_eq!(extra.get::<String>("db").unwrap(), "movies");
+        assert_eq!(extra.get::<String>("imp_user").unwrap(), "alice");
+        let metadata: BoltMap = extra.get("tx_metadata").unwrap();
+        assert_eq!(metadata.get::<String>("app").unwrap(), "billing");
+        assert!(TxnConfig::new().extra(&config).value.is_empty());
+    }
+
+    #[tokio::test]
+    async fn should_begin_with_the_txn_config() {
+        let (uri, requests) =
+            recording_stub_server(vec![vec![success()], vec![success()], vec![success()]]).await;
+        let graph = connect(&uri).await;
+
+        let txn = graph
+            .start_txn_with(TxnConfig::new().timeout(Duration::from_secs(1)))
+            .await
+            .unwrap();
+        txn.commit().await.unwrap();
+
+        let requests = requests.lock().unwrap();
+        assert_eq!(&requests[1][..2], &[0xB1, 0x11]);
+        assert!(contains(&requests[1], b"\x8Atx_timeout\xC9\x03\xE8"));
+        assert!(contains(&requests[1], b"\x82db\x85neo4j"));
+    }
+
+    #[tokio::test]
+    async fn should_run_auto_commit_queries_with_the_txn_config() {
+        let (uri, requests) =
+            recording_stub_server(vec![vec![success()], vec![success()], vec![success()]]).await;
+        let graph = connect(&uri).await;
+
+        let txn_config = TxnConfig::new()
+            .metadata("app", "billing")
+            .db("movies")
+            .bookmarks(vec!["bookmark-1".into()]);
+        graph
+            .run_with(query("MATCH (n) RETURN n"), txn_config)
+            .await
+            .unwrap();
+
+        let requests = requests.lock().unwrap();
+        for value in ["tx_metadata", "billing", "movies", "bookmark-1"] {
+            assert!(contains(&requests[1], value.as_bytes()));
+        }
+        assert!(!contains(&requests[1], b"neo4j"));
+    }
+
+    #[tokio::test]
+    async fn should_impersonate_the_user_of_the_query() {
+        let (uri, requests) =
+            recording_stub_server(vec![vec![success()], vec![success()], vec![success()]]).await;
+        let graph = connect(&uri).await;
+
+        graph
+            .run_with(
+                query("MATCH (n) RETURN n").impersonate("bob"),
+                TxnConfig::new().impersonate("alice"),
+            )
+            .await
+            .unwrap();
+
+        let requests = requests.lock().unwrap();
+        assert!(contains(&requests[1], b"\x88imp_user\x83bob"));
+        assert!(!contains(&requests[1], b"alice"));
+    }
+
+    #[tokio::test]
+    async fn should_fail_with_metadata_which_cannot_be_serialized() {
+        let uri = stub_server(vec![vec![success()]]).await;
+        let graph = connect(&uri).await;
+        let mut invalid = std::collections::HashMap::new();
+        invalid.insert(vec![1], 1);
+
+        let result = graph
+            .start_txn_with(TxnConfig::new().metadata("invalid", &invalid))
+            .await;
+
+        assert!(matches!(result, Err(Error::SerializationError(_))));
+    }
+
+    /// Waits until the stub server received `count` requests, for requests sent by spawned tasks
+    async fn received(requests: &Requests, count: usize) -> Vec<Bytes> {
+        for _ in 0..100 {
+            if requests.lock().unwrap().len() >= count {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        requests.lock().unwrap().clone()
+    }
+
+    #[tokio::test]
+    async fn should_roll_back_dropped_transactions() {
+        let (uri, requests) = recording_stub_server(vec![
+            vec![success()],
+            vec![success()],
+            vec![success()],
+            vec![success()],
+            vec![success()],
+        ])
+        .await;
+        let graph = connect(&uri).await;
+
+        let txn = graph.start_txn().await.unwrap();
+        txn.run(query("CREATE (n)")).await.unwrap();
+        assert_eq!(txn.state(), TxnState::Active);
+        drop(txn);
+
+        let requests = received(&requests, 5).await;
+        assert_eq!(requests.len(), 5);
+        assert_eq!(requests[4], request_bytes(BoltRequest::rollback()));
+    }
+
+    #[tokio::test]
+    async fn should_reject_queries_after_a_failure() {
+        let (uri, requests) = recording_stub_server(vec![
+            vec![success()],
+            vec![success()],
+            vec![failure("Neo.ClientError.Statement.SyntaxError")],
+            vec![ignored()],
+            vec![success()],
+        ])
+        .await;
+        let graph = connect(&uri).await;
+
+        let txn = graph.start_txn().await.unwrap();
+        let result = txn.run(query("CRATE (n)")).await;
+        assert!(matches!(result, Err(Error::Neo4jError(_))));
+        assert_eq!(txn.state(), TxnState::Failed);
+
+        let result = txn.run(query("CREATE (n)")).await;
+        assert!(matches!(result, Err(Error::InvalidTransactionState(_))));
+        let result = txn.execute(query("MATCH (n) RETURN n")).await;
+        assert!(matches!(result, Err(Error::InvalidTransactionState(_))));
+        let result = txn.share().commit().await;
+        assert!(matches!(result, Err(Error::InvalidTransactionState(_))));
+        txn.rollback().await.unwrap();
+
+        let requests = requests.lock().unwrap();
+        assert_eq!(requests.len(), 5);
+        assert_eq!(requests[4], request_bytes(BoltRequest::reset()));
+    }
+
+    #[tokio::test]
+    async fn should_reset_dropped_failed_transactions() {
+        let (uri, requests) = recording_stub_server(vec![
+            vec![success()],
+            vec![success()],
+            vec![failure("Neo.ClientError.Statement.SyntaxError")],
+            vec![ignored()],
+            vec![success()],
+        ])
+        .await;
+        let graph = connect(&uri).await;
+
+        let txn = graph.start_txn().await.unwrap();
+        assert!(txn.run(query("CRATE (n)")).await.is_err());
+        drop(txn);
+
+        let requests = received(&requests, 5).await;
+        assert_eq!(requests[4], request_bytes(BoltRequest::reset()));
+    }
+
+    #[tokio::test]
+    async fn should_not_use_finished_transactions() {
+        let (uri, requests) = recording_stub_server(vec![
+            vec![success()],
+            vec![success()],
+            vec![success()],
+            vec![success()],
+        ])
+        .await;
+        let graph = connect(&uri).await;
+
+        let txn = graph.start_txn().await.unwrap();
+        let shared = txn.share();
+        txn.commit().await.unwrap();
+        assert_eq!(shared.state(), TxnState::Committed);
+        let result = shared.run(query("CREATE (n)")).await;
+        assert!(matches!(result, Err(Error::InvalidTransactionState(_))));
+        let result = shared.rollback().await;
+        assert!(matches!(result, Err(Error::InvalidTransactionState(_))));
+
+        let requests = received(&requests, 4).await;
+        assert_eq!(requests.len(), 3);
+        assert_eq!(requests[2], request_bytes(BoltRequest::commit()));
+    }
+
+    fn started(qid: i64) -> Bytes {
+        let mut metadata = BoltMap::default();
+        metadata.put("fields".into(), vec!["n"].into());
+        metadata.put("qid".into(), qid.into());
+        success_with(metadata)
+    }
+
+    fn has_more() -> Bytes {
+        let mut metadata = BoltMap::default();
+        metadata.put("has_more".into(), true.into());
+        success_with(metadata)
+    }
+
+    fn row(value: i64) -> Bytes {
+        let mut values = BoltList::new();
+        values.push(value.into());
+        record(values)
+    }
+
+    #[tokio::test]
+    async fn should_consume_streams_in_any_order() {
+        let uri = stub_server(vec![
+            vec![success()],
+            vec![success()],
+            vec![started(0)],
+            vec![row(1), has_more()],
+            vec![started(1)],
+            vec![row(10), row(20), success()],
+            vec![row(2), success()],
+            vec![success()],
+        ])
+        .await;
+        let config = config()
+            .uri(&uri)
+            .user("user")
+            .password("pass")
+            .fetch_size(1)
+            .max_connections(1)
+            .build()
+            .unwrap();
+        let graph = Graph::connect(config).await.unwrap();
+        let txn = graph.start_txn().await.unwrap();
+        let value = |row: Option<crate::Row>| row.unwrap().get::<i64>("n").unwrap();
+
+        let mut first = txn
+            .execute(query("UNWIND [1, 2] AS n RETURN n"))
+            .await
+            .unwrap();
+        // the PULL is sent but the rows are not read yet when the second query runs
+        assert!(first.next().now_or_never().is_none());
+        let mut second = txn
+            .execute(query("UNWIND [10, 20] AS n RETURN n"))
+            .await
+            .unwrap();
+
+        assert_eq!(value(second.next().await.unwrap()), 10);
+        assert_eq!(value(first.next().await.unwrap()), 1);
+        assert_eq!(value(first.next().await.unwrap()), 2);
+        assert_eq!(value(second.next().await.unwrap()), 20);
+        assert!(first.next().await.unwrap().is_none());
+        assert!(second.next().await.unwrap().is_none());
+        txn.commit().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn should_pipeline_begin_run_and_discard() {
+        // the responses are only sent once all requests arrived
+        let uri = stub_server(vec![
+            vec![success()],
+            vec![],
+            vec![],
+            vec![success(), success(), success()],
+            vec![],
+            vec![started(0), row(1), success()],
+            vec![success()],
+        ])
+        .await;
+        let graph = connect(&uri).await;
+
+        let work = async {
+            let txn = graph.start_txn().await.unwrap();
+            txn.run(query("CREATE (n)")).await.unwrap();
+            let mut result = txn.execute(query("RETURN 1 AS n")).await.unwrap();
+            assert_eq!(
+                result.next().await.unwrap().unwrap().get::<i64>("n"),
+                Some(1)
+            );
+            assert!(result.next().await.unwrap().is_none());
+            txn.commit().await.unwrap();
+        };
+
+        tokio::time::timeout(Duration::from_secs(5), work)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn should_report_a_failed_begin_with_the_first_query() {
+        let uri = stub_server(vec![
+            vec![success()],
+            vec![failure("Neo.ClientError.Database.DatabaseNotFound")],
+            vec![ignored()],
+            vec![ignored()],
+            vec![success()],
+        ])
+        .await;
+        let graph = connect(&uri).await;
+
+        let txn = graph.start_txn().await.unwrap();
+        let result = txn.run(query("CREATE (n)")).await;
+
+        match result {
+            Err(Error::Neo4jError(e)) => assert_eq!(e.title, "DatabaseNotFound"),
+            result => panic!("expected the failure of BEGIN, got {:?}", result),
         }
+        assert_eq!(txn.state(), TxnState::Failed);
+        txn.rollback().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn should_run_a_batch_in_one_round_trip() {
+        let uri = stub_server(vec![
+            vec![success()],
+            vec![],
+            vec![],
+            vec![],
+            vec![],
+            vec![success(), success(), success(), success(), success()],
+            vec![success()],
+        ])
+        .await;
+        let graph = connect(&uri).await;
+
+        let work = async {
+            let txn = graph.start_txn().await.unwrap();
+            let summaries = txn
+                .run_batch(vec![query("CREATE (n)"), query("CREATE (m)")])
+                .await
+                .unwrap();
+            assert_eq!(summaries.len(), 2);
+            txn.commit().await.unwrap();
+        };
+
+        tokio::time::timeout(Duration::from_secs(5), work)
+            .await
+            .unwrap();
     }
 }
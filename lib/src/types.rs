@@ -100,7 +100,7 @@ impl BoltType {
         }
     }
 
-    fn parse(version: Version, input: Rc<RefCell<Bytes>>) -> Result<BoltType> {
+    pub(crate) fn parse(version: Version, input: Rc<RefCell<Bytes>>) -> Result<BoltType> {
         let bolt_type = match input {
             input if BoltInteger::can_parse(version, input.clone()) => {
                 BoltType::Integer(BoltInteger::parse(version, input)?)
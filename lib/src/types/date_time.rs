@@ -1,11 +1,23 @@
 use crate::errors::Error;
 use crate::types::*;
+use crate::version::Version;
+use bytes::*;
 use chrono::{DateTime, FixedOffset, NaiveDateTime, Offset, Timelike};
 use neo4rs_macros::BoltStruct;
+use std::cell::RefCell;
 use std::convert::TryInto;
+use std::rc::Rc;
 
-#[derive(Debug, PartialEq, Clone, BoltStruct)]
-#[signature(0xB3, 0x46)]
+pub const LEGACY_DATE_TIME_SIGNATURE: u8 = 0x46;
+pub const DATE_TIME_SIGNATURE: u8 = 0x49;
+pub const LEGACY_DATE_TIME_ZONE_ID_SIGNATURE: u8 = 0x66;
+pub const DATE_TIME_ZONE_ID_SIGNATURE: u8 = 0x69;
+
+/// A datetime with a fixed offset, `seconds` are local to the offset.
+///
+/// Bolt 5.0, and 4.3 or 4.4 with the `utc` patch, send the seconds as a UTC instant instead, the
+/// conversion happens while serializing and parsing.
+#[derive(Debug, PartialEq, Clone)]
 pub struct BoltDateTime {
     seconds: BoltInteger,
     nanoseconds: BoltInteger,
@@ -19,14 +31,109 @@ pub struct BoltLocalDateTime {
     nanoseconds: BoltInteger,
 }
 
-#[derive(Debug, PartialEq, Clone, BoltStruct)]
-#[signature(0xB3, 0x66)]
+/// A datetime with a zone id.
+///
+/// Up to Bolt 4.4 `seconds` are local to the zone. Bolt 5.0 and the `utc` patch send them as a
+/// UTC instant and the
+/// driver has no timezone database to map them back, so on those versions the
+/// [`NaiveDateTime`] is interpreted as UTC in both directions.
+#[derive(Debug, PartialEq, Clone)]
 pub struct BoltDateTimeZoneId {
     seconds: BoltInteger,
     nanoseconds: BoltInteger,
     tz_id: BoltString,
 }
 
+fn signature(version: Version, legacy: u8, utc: u8) -> u8 {
+    if version.uses_utc_datetime() {
+        utc
+    } else {
+        legacy
+    }
+}
+
+impl BoltDateTime {
+    pub fn into_bytes(self, version: Version) -> Result<Bytes> {
+        let seconds = if version.uses_utc_datetime() {
+            self.seconds - self.tz_offset_seconds.clone()
+        } else {
+            self.seconds
+        };
+        let mut bytes = BytesMut::new();
+        bytes.put_u8(0xB3);
+        bytes.put_u8(signature(
+            version,
+            LEGACY_DATE_TIME_SIGNATURE,
+            DATE_TIME_SIGNATURE,
+        ));
+        bytes.put(seconds.into_bytes(version)?);
+        bytes.put(self.nanoseconds.into_bytes(version)?);
+        bytes.put(self.tz_offset_seconds.into_bytes(version)?);
+        Ok(bytes.freeze())
+    }
+
+    pub fn can_parse(version: Version, input: Rc<RefCell<Bytes>>) -> bool {
+        let input = input.borrow();
+        input.len() >= 2
+            && input[0] == 0xB3
+            && input[1] == signature(version, LEGACY_DATE_TIME_SIGNATURE, DATE_TIME_SIGNATURE)
+    }
+
+    pub fn parse(version: Version, input: Rc<RefCell<Bytes>>) -> Result<BoltDateTime> {
+        input.borrow_mut().advance(2);
+        let seconds = BoltInteger::parse(version, input.clone())?;
+        let nanoseconds = BoltInteger::parse(version, input.clone())?;
+        let tz_offset_seconds = BoltInteger::parse(version, input)?;
+        let seconds = if version.uses_utc_datetime() {
+            seconds + tz_offset_seconds.clone()
+        } else {
+            seconds
+        };
+        Ok(BoltDateTime {
+            seconds,
+            nanoseconds,
+            tz_offset_seconds,
+        })
+    }
+}
+
+impl BoltDateTimeZoneId {
+    pub fn into_bytes(self, version: Version) -> Result<Bytes> {
+        let mut bytes = BytesMut::new();
+        bytes.put_u8(0xB3);
+        bytes.put_u8(signature(
+            version,
+            LEGACY_DATE_TIME_ZONE_ID_SIGNATURE,
+            DATE_TIME_ZONE_ID_SIGNATURE,
+        ));
+        bytes.put(self.seconds.into_bytes(version)?);
+        bytes.put(self.nanoseconds.into_bytes(version)?);
+        bytes.put(self.tz_id.into_bytes(version)?);
+        Ok(bytes.freeze())
+    }
+
+    pub fn can_parse(version: Version, input: Rc<RefCell<Bytes>>) -> bool {
+        let input = input.borrow();
+        input.len() >= 2
+            && input[0] == 0xB3
+            && input[1]
+                == signature(
+                    version,
+                    LEGACY_DATE_TIME_ZONE_ID_SIGNATURE,
+                    DATE_TIME_ZONE_ID_SIGNATURE,
+                )
+    }
+
+    pub fn parse(version: Version, input: Rc<RefCell<Bytes>>) -> Result<BoltDateTimeZoneId> {
+        input.borrow_mut().advance(2);
+        Ok(BoltDateTimeZoneId {
+            seconds: BoltInteger::parse(version, input.clone())?,
+            nanoseconds: BoltInteger::parse(version, input.clone())?,
+            tz_id: BoltString::parse(version, input)?,
+        })
+    }
+}
+
 impl From<(NaiveDateTime, &str)> for BoltDateTimeZoneId {
     fn from(value: (NaiveDateTime, &str)) -> Self {
         let seconds = value.0.timestamp().into();
@@ -101,10 +208,6 @@ impl TryInto<DateTime<FixedOffset>> for BoltDateTime {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::version::Version;
-    use bytes::*;
-    use std::cell::RefCell;
-    use std::rc::Rc;
 
     #[test]
     fn should_serialize_a_datetime() {
@@ -194,4 +297,47 @@ mod tests {
         assert_eq!(datetime.to_string(), "2015-07-01 08:59:60.123");
         assert_eq!(zone_id, "Europe/Paris");
     }
+
+    #[test]
+    fn should_serialize_a_datetime_as_utc_on_bolt_5() {
+        let date: BoltDateTime = DateTime::parse_from_rfc2822("Wed, 24 Jun 2015 12:50:35 +0100")
+            .unwrap()
+            .into();
+
+        assert_eq!(
+            date.into_bytes(Version::V5_0).unwrap(),
+            Bytes::from_static(&[
+                0xB3, 0x49, 0xCA, 0x55, 0x8A, 0x99, 0x8B, 0x00, 0xC9, 0x0E, 0x10,
+            ])
+        );
+    }
+
+    #[test]
+    fn should_deserialize_a_utc_datetime_on_bolt_5() {
+        let bytes = Rc::new(RefCell::new(Bytes::from_static(&[
+            0xB3, 0x49, 0xCA, 0x55, 0x8A, 0x99, 0x8B, 0x00, 0xC9, 0x0E, 0x10,
+        ])));
+
+        assert!(!BoltDateTime::can_parse(Version::V4_4, bytes.clone()));
+        let datetime: DateTime<FixedOffset> = BoltDateTime::parse(Version::V5_0, bytes)
+            .unwrap()
+            .try_into()
+            .unwrap();
+
+        assert_eq!(datetime.to_rfc2822(), "Wed, 24 Jun 2015 12:50:35 +0100");
+    }
+
+    #[test]
+    fn should_use_utc_signature_for_zoneid_on_bolt_5() {
+        let datetime =
+            NaiveDateTime::parse_from_str("2015-07-01 08:59:60.123", "%Y-%m-%d %H:%M:%S%.f")
+                .unwrap();
+        let date: BoltDateTimeZoneId = (datetime, "Europe/Paris").into();
+
+        let bytes = date.clone().into_bytes(Version::V5_0).unwrap();
+        assert_eq!(&bytes[..2], &[0xB3, 0x69]);
+
+        let parsed = BoltDateTimeZoneId::parse(Version::V5_0, Rc::new(RefCell::new(bytes)));
+        assert_eq!(parsed.unwrap(), date);
+    }
 }
@@ -31,6 +31,10 @@ impl BoltMap {
         self.value.len()
     }
 
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.value.contains_key(&BoltString::new(key))
+    }
+
     pub fn put(&mut self, key: BoltString, value: BoltType) {
         self.value.insert(key, value);
     }
@@ -1,20 +1,31 @@
+use crate::errors::*;
 use crate::types::*;
-use neo4rs_macros::BoltStruct;
+use crate::version::Version;
+use bytes::*;
+use std::cell::RefCell;
+use std::rc::Rc;
 
-#[derive(Debug, PartialEq, Clone, BoltStruct)]
-#[signature(0xB3, 0x4E)]
+pub const SIGNATURE: u8 = 0x4E;
+
+/// A node as sent by the server.
+///
+/// Bolt 5.0 added a string `element_id`, for older protocol versions it is derived from `id`.
+#[derive(Debug, PartialEq, Clone)]
 pub struct BoltNode {
     pub id: BoltInteger,
     pub labels: BoltList,
     pub properties: BoltMap,
+    pub element_id: BoltString,
 }
 
 impl BoltNode {
     pub fn new(id: BoltInteger, labels: BoltList, properties: BoltMap) -> Self {
+        let element_id = id.value.to_string().into();
         BoltNode {
             id,
             labels,
             properties,
+            element_id,
         }
     }
 }
@@ -25,6 +36,55 @@ impl BoltNode {
     }
 }
 
+impl BoltNode {
+    fn marker(version: Version) -> u8 {
+        if version.supports_element_ids() {
+            0xB4
+        } else {
+            0xB3
+        }
+    }
+
+    pub fn into_bytes(self, version: Version) -> Result<Bytes> {
+        let id = self.id.into_bytes(version)?;
+        let labels = self.labels.into_bytes(version)?;
+        let properties = self.properties.into_bytes(version)?;
+        let mut bytes = BytesMut::with_capacity(2 + id.len() + labels.len() + properties.len());
+        bytes.put_u8(Self::marker(version));
+        bytes.put_u8(SIGNATURE);
+        bytes.put(id);
+        bytes.put(labels);
+        bytes.put(properties);
+        if version.supports_element_ids() {
+            bytes.put(self.element_id.into_bytes(version)?);
+        }
+        Ok(bytes.freeze())
+    }
+
+    pub fn can_parse(version: Version, input: Rc<RefCell<Bytes>>) -> bool {
+        let input = input.borrow();
+        input.len() >= 2 && input[0] == Self::marker(version) && input[1] == SIGNATURE
+    }
+
+    pub fn parse(version: Version, input: Rc<RefCell<Bytes>>) -> Result<BoltNode> {
+        input.borrow_mut().advance(2);
+        let id = BoltInteger::parse(version, input.clone())?;
+        let labels = BoltList::parse(version, input.clone())?;
+        let properties = BoltMap::parse(version, input.clone())?;
+        let element_id = if version.supports_element_ids() {
+            BoltString::parse(version, input)?
+        } else {
+            id.value.to_string().into()
+        };
+        Ok(BoltNode {
+            id,
+            labels,
+            properties,
+            element_id,
+        })
+    }
+}
+
 impl From<BoltNode> for BoltType {
     fn from(value: BoltNode) -> Self {
         BoltType::Node(value)
@@ -34,10 +94,6 @@ impl From<BoltNode> for BoltType {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::version::Version;
-    use bytes::*;
-    use std::cell::RefCell;
-    use std::rc::Rc;
 
     #[test]
     fn should_deserialize_a_node() {
@@ -54,6 +110,7 @@ mod tests {
             node.properties,
             vec![("name".into(), "Mark".into())].into_iter().collect()
         );
+        assert_eq!(node.element_id, BoltString::new("19"));
     }
 
     #[test]
@@ -61,11 +118,7 @@ mod tests {
         let id = BoltInteger::new(19);
         let labels = vec!["Person".into()].into();
         let properties = vec![("name".into(), "Mark".into())].into_iter().collect();
-        let node = BoltNode {
-            id,
-            labels,
-            properties,
-        };
+        let node = BoltNode::new(id, labels, properties);
 
         let bytes: Bytes = node.into_bytes(Version::V4_1).unwrap();
 
@@ -77,4 +130,40 @@ mod tests {
             ])
         );
     }
+
+    #[test]
+    fn should_deserialize_a_node_with_element_id() {
+        let input = Rc::new(RefCell::new(Bytes::from_static(&[
+            0xB4, 0x4E, 0x13, 0x91, 0x86, 0x50, 0x65, 0x72, 0x73, 0x6F, 0x6E, 0xA1, 0x84, 0x6E,
+            0x61, 0x6D, 0x65, 0x84, 0x4D, 0x61, 0x72, 0x6B, 0x84, 0x34, 0x3A, 0x31, 0x39,
+        ])));
+
+        assert!(!BoltNode::can_parse(Version::V4_4, input.clone()));
+        assert!(BoltNode::can_parse(Version::V5_0, input.clone()));
+        let node: BoltNode = BoltNode::parse(Version::V5_0, input).unwrap();
+
+        assert_eq!(node.id, BoltInteger::new(19));
+        assert_eq!(node.labels, vec!["Person".into()].into());
+        assert_eq!(node.element_id, BoltString::new("4:19"));
+    }
+
+    #[test]
+    fn should_serialize_a_node_with_element_id() {
+        let mut node = BoltNode::new(
+            BoltInteger::new(19),
+            vec!["Person".into()].into(),
+            vec![("name".into(), "Mark".into())].into_iter().collect(),
+        );
+        node.element_id = BoltString::new("4:19");
+
+        let bytes: Bytes = node.into_bytes(Version::V5_0).unwrap();
+
+        assert_eq!(
+            bytes,
+            Bytes::from_static(&[
+                0xB4, 0x4E, 0x13, 0x91, 0x86, 0x50, 0x65, 0x72, 0x73, 0x6F, 0x6E, 0xA1, 0x84, 0x6E,
+                0x61, 0x6D, 0x65, 0x84, 0x4D, 0x61, 0x72, 0x6B, 0x84, 0x34, 0x3A, 0x31, 0x39,
+            ])
+        );
+    }
 }
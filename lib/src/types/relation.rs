@@ -1,30 +1,67 @@
+use crate::errors::*;
 use crate::types::*;
-use neo4rs_macros::BoltStruct;
+use crate::version::Version;
+use bytes::*;
+use std::cell::RefCell;
+use std::rc::Rc;
 
-#[derive(Debug, PartialEq, Clone, BoltStruct)]
-#[signature(0xB5, 0x52)]
+pub const RELATION_SIGNATURE: u8 = 0x52;
+pub const UNBOUNDED_RELATION_SIGNATURE: u8 = 0x72;
+
+/// A relationship as sent by the server.
+///
+/// Bolt 5.0 added string element ids, for older protocol versions they are derived from the
+/// integer ids.
+#[derive(Debug, PartialEq, Clone)]
 pub struct BoltRelation {
     pub id: BoltInteger,
     pub start_node_id: BoltInteger,
     pub end_node_id: BoltInteger,
     pub typ: BoltString,
     pub properties: BoltMap,
+    pub element_id: BoltString,
+    pub start_node_element_id: BoltString,
+    pub end_node_element_id: BoltString,
 }
 
-#[derive(Debug, PartialEq, Clone, BoltStruct)]
-#[signature(0xB3, 0x72)]
+/// A relationship within a path, without start or end node information.
+#[derive(Debug, PartialEq, Clone)]
 pub struct BoltUnboundedRelation {
     pub id: BoltInteger,
     pub typ: BoltString,
     pub properties: BoltMap,
+    pub element_id: BoltString,
+}
+
+impl BoltRelation {
+    pub fn new(
+        id: BoltInteger,
+        start_node_id: BoltInteger,
+        end_node_id: BoltInteger,
+        typ: BoltString,
+        properties: BoltMap,
+    ) -> Self {
+        BoltRelation {
+            element_id: id.value.to_string().into(),
+            start_node_element_id: start_node_id.value.to_string().into(),
+            end_node_element_id: end_node_id.value.to_string().into(),
+            id,
+            start_node_id,
+            end_node_id,
+            typ,
+            properties,
+        }
+    }
 }
 
 impl BoltUnboundedRelation {
     pub fn new(id: BoltInteger, typ: BoltString, properties: BoltMap) -> Self {
+        let element_id = id.value.to_string().into();
         BoltUnboundedRelation {
             id,
             typ,
             properties,
+            element_id,
         }
     }
 }
@@ -41,6 +78,114 @@ impl BoltUnboundedRelation {
     }
 }
 
+impl BoltRelation {
+    fn marker(version: Version) -> u8 {
+        if version.supports_element_ids() {
+            0xB8
+        } else {
+            0xB5
+        }
+    }
+
+    pub fn into_bytes(self, version: Version) -> Result<Bytes> {
+        let mut bytes = BytesMut::new();
+        bytes.put_u8(Self::marker(version));
+        bytes.put_u8(RELATION_SIGNATURE);
+        bytes.put(self.id.into_bytes(version)?);
+        bytes.put(self.start_node_id.into_bytes(version)?);
+        bytes.put(self.end_node_id.into_bytes(version)?);
+        bytes.put(self.typ.into_bytes(version)?);
+        bytes.put(self.properties.into_bytes(version)?);
+        if version.supports_element_ids() {
+            bytes.put(self.element_id.into_bytes(version)?);
+            bytes.put(self.start_node_element_id.into_bytes(version)?);
+            bytes.put(self.end_node_element_id.into_bytes(version)?);
+        }
+        Ok(bytes.freeze())
+    }
+
+    pub fn can_parse(version: Version, input: Rc<RefCell<Bytes>>) -> bool {
+        let input = input.borrow();
+        input.len() >= 2 && input[0] == Self::marker(version) && input[1] == RELATION_SIGNATURE
+    }
+
+    pub fn parse(version: Version, input: Rc<RefCell<Bytes>>) -> Result<BoltRelation> {
+        input.borrow_mut().advance(2);
+        let id = BoltInteger::parse(version, input.clone())?;
+        let start_node_id = BoltInteger::parse(version, input.clone())?;
+        let end_node_id = BoltInteger::parse(version, input.clone())?;
+        let typ = BoltString::parse(version, input.clone())?;
+        let properties = BoltMap::parse(version, input.clone())?;
+        if version.supports_element_ids() {
+            Ok(BoltRelation {
+                id,
+                start_node_id,
+                end_node_id,
+                typ,
+                properties,
+                element_id: BoltString::parse(version, input.clone())?,
+                start_node_element_id: BoltString::parse(version, input.clone())?,
+                end_node_element_id: BoltString::parse(version, input)?,
+            })
+        } else {
+            Ok(BoltRelation::new(
+                id,
+                start_node_id,
+                end_node_id,
+                typ,
+                properties,
+            ))
+        }
+    }
+}
+
+impl BoltUnboundedRelation {
+    fn marker(version: Version) -> u8 {
+        if version.supports_element_ids() {
+            0xB4
+        } else {
+            0xB3
+        }
+    }
+
+    pub fn into_bytes(self, version: Version) -> Result<Bytes> {
+        let mut bytes = BytesMut::new();
+        bytes.put_u8(Self::marker(version));
+        bytes.put_u8(UNBOUNDED_RELATION_SIGNATURE);
+        bytes.put(self.id.into_bytes(version)?);
+        bytes.put(self.typ.into_bytes(version)?);
+        bytes.put(self.properties.into_bytes(version)?);
+        if version.supports_element_ids() {
+            bytes.put(self.element_id.into_bytes(version)?);
+        }
+        Ok(bytes.freeze())
+    }
+
+    pub fn can_parse(version: Version, input: Rc<RefCell<Bytes>>) -> bool {
+        let input = input.borrow();
+        input.len() >= 2
+            && input[0] == Self::marker(version)
+            && input[1] == UNBOUNDED_RELATION_SIGNATURE
+    }
+
+    pub fn parse(version: Version, input: Rc<RefCell<Bytes>>) -> Result<BoltUnboundedRelation> {
+        input.borrow_mut().advance(2);
+        let id = BoltInteger::parse(version, input.clone())?;
+        let typ = BoltString::parse(version, input.clone())?;
+        let properties = BoltMap::parse(version, input.clone())?;
+        if version.supports_element_ids() {
+            Ok(BoltUnboundedRelation {
+                id,
+                typ,
+                properties,
+                element_id: BoltString::parse(version, input)?,
+            })
+        } else {
+            Ok(BoltUnboundedRelation::new(id, typ, properties))
+        }
+    }
+}
+
 impl From<BoltRelation> for BoltType {
     fn from(value: BoltRelation) -> Self {
         BoltType::Relation(value)
@@ -56,9 +201,6 @@ impl From<BoltUnboundedRelation> for BoltType {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use bytes::*;
-    use std::cell::RefCell;
-    use std::rc::Rc;
 
     #[test]
     fn should_serialize_a_relation() {
@@ -68,13 +210,7 @@ mod tests {
         let typ = BoltString::new("rel");
         let properties = vec![("name".into(), "Mark".into())].into_iter().collect();
 
-        let relation = BoltRelation {
-            id,
-            start_node_id,
-            end_node_id,
-            typ,
-            properties,
-        };
+        let relation = BoltRelation::new(id, start_node_id, end_node_id, typ, properties);
 
         let bytes: Bytes = relation.into_bytes(Version::V4_1).unwrap();
 
@@ -104,6 +240,26 @@ mod tests {
             relation.properties,
             vec![("name".into(), "Mark".into())].into_iter().collect()
         );
+        assert_eq!(relation.element_id, BoltString::new("42"));
+        assert_eq!(relation.start_node_element_id, BoltString::new("1"));
+        assert_eq!(relation.end_node_element_id, BoltString::new("2"));
+    }
+
+    #[test]
+    fn should_deserialize_a_relation_with_element_ids() {
+        let input = Rc::new(RefCell::new(Bytes::from_static(&[
+            0xB8, 0x52, 0x2A, 0x01, 0x02, 0x83, 0x72, 0x65, 0x6C, 0xA0, 0x84, 0x34, 0x3A, 0x34,
+            0x32, 0x83, 0x34, 0x3A, 0x31, 0x83, 0x34, 0x3A, 0x32,
+        ])));
+
+        assert!(!BoltRelation::can_parse(Version::V4_4, input.clone()));
+        let relation: BoltRelation = BoltRelation::parse(Version::V5_0, input).unwrap();
+
+        assert_eq!(relation.id, BoltInteger::new(42));
+        assert_eq!(relation.typ, BoltString::new("rel"));
+        assert_eq!(relation.element_id, BoltString::new("4:42"));
+        assert_eq!(relation.start_node_element_id, BoltString::new("4:1"));
+        assert_eq!(relation.end_node_element_id, BoltString::new("4:2"));
     }
 
     #[test]
@@ -141,4 +297,21 @@ mod tests {
             vec![("name".into(), "Mark".into())].into_iter().collect()
         );
     }
+
+    #[test]
+    fn should_round_trip_an_unbounded_relation_with_element_id() {
+        let mut relation = BoltUnboundedRelation::new(
+            BoltInteger::new(42),
+            BoltString::new("rel"),
+            BoltMap::default(),
+        );
+        relation.element_id = BoltString::new("4:42");
+
+        let bytes = relation.clone().into_bytes(Version::V5_0).unwrap();
+        assert_eq!(&bytes[..2], &[0xB4, 0x72]);
+
+        let input = Rc::new(RefCell::new(bytes));
+        let parsed = BoltUnboundedRelation::parse(Version::V5_0, input).unwrap();
+        assert_eq!(parsed, relation);
+    }
 }
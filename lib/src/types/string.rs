@@ -99,7 +99,7 @@ impl BoltString {
         };
         let byte_array = input.split_to(length).to_vec();
         let string_value = std::string::String::from_utf8(byte_array)
-            .map_err(|e| Error::DeserializationError(e.to_string()))?;
+            .map_err(|e| Error::Deserialize(serde::de::Error::custom(e)))?;
         Ok(string_value.into())
     }
 }
@@ -122,6 +122,13 @@ mod tests {
         assert_eq!(s, "".into());
     }
 
+    #[test]
+    fn should_reject_invalid_utf8() {
+        let input = Rc::new(RefCell::new(Bytes::from_static(&[0x81, 0xFF])));
+        let result = BoltString::parse(Version::V4_1, input);
+        assert!(matches!(result, Err(Error::Deserialize(_))));
+    }
+
     #[test]
     fn should_serialize_tiny_string() {
         let s = BoltString::new("a");
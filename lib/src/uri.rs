@@ -0,0 +1,199 @@
+use crate::errors::*;
+
+const DEFAULT_PORT: u16 = 7687;
+
+/// Whether and how the connection to the server is encrypted
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encryption {
+    /// Plain TCP, `bolt://` and `neo4j://`
+    None,
+    /// TLS with certificates signed by a trusted CA, `bolt+s://` and `neo4j+s://`
+    Verified,
+    /// TLS accepting any certificate, including self-signed ones, `bolt+ssc://` and
+    /// `neo4j+ssc://`
+    SelfSigned,
+}
+
+/// A parsed connection uri of the form `scheme://host:port?routing_context`.
+///
+/// `bolt` schemes connect to a single server while `neo4j` schemes may route queries within a
+/// cluster. A bare `host:port` is accepted as `bolt://host:port`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Uri {
+    pub(crate) routing: bool,
+    pub(crate) encryption: Encryption,
+    pub(crate) host: String,
+    pub(crate) port: u16,
+    pub(crate) routing_context: Vec<(String, String)>,
+}
+
+impl Uri {
+    pub fn parse(uri: &str) -> Result<Uri> {
+        let (scheme, rest) = match uri.find("://") {
+            Some(index) => (&uri[..index], &uri[index + 3..]),
+            None => ("bolt", uri),
+        };
+        let (routing, encryption) = match scheme {
+            "bolt" => (false, Encryption::None),
+            "bolt+s" => (false, Encryption::Verified),
+            "bolt+ssc" => (false, Encryption::SelfSigned),
+            "neo4j" => (true, Encryption::None),
+            "neo4j+s" => (true, Encryption::Verified),
+            "neo4j+ssc" => (true, Encryption::SelfSigned),
+            _ => return Err(Error::UnsupportedScheme(scheme.to_owned())),
+        };
+
+        let (address, query) = match rest.find('?') {
+            Some(index) => (&rest[..index], Some(&rest[index + 1..])),
+            None => (rest, None),
+        };
+        let address = address.trim_end_matches('/');
+        let (host, port) = Self::split_host_port(address)
+            .ok_or_else(|| Error::InvalidUri(format!("invalid address in {}", uri)))?;
+
+        let routing_context = match query {
+            Some(query) if !routing => {
+                return Err(Error::InvalidUri(format!(
+                    "routing context {} is only supported by neo4j schemes",
+                    query
+                )))
+            }
+            Some(query) => query
+                .split('&')
+                .filter(|pair| !pair.is_empty())
+                .map(|pair| match pair.find('=') {
+                    Some(index) if index > 0 => {
+                        Ok((pair[..index].to_owned(), pair[index + 1..].to_owned()))
+                    }
+                    _ => Err(Error::InvalidUri(format!(
+                        "invalid routing context {}",
+                        pair
+                    ))),
+                })
+                .collect::<Result<Vec<_>>>()?,
+            None => Vec::new(),
+        };
+
+        Ok(Uri {
+            routing,
+            encryption,
+            host,
+            port,
+            routing_context,
+        })
+    }
+
+    /// Splits a `host:port` address, as found in uris and routing tables
+    pub(crate) fn split_host_port(address: &str) -> Option<(String, u16)> {
+        let (host, port) = if let Some(rest) = address.strip_prefix('[') {
+            let end = rest.find(']')?;
+            let port = rest[end + 1..].strip_prefix(':');
+            if port.is_none() && end + 1 != rest.len() {
+                return None;
+            }
+            (&rest[..end], port)
+        } else {
+            match address.rfind(':') {
+                Some(index) => (&address[..index], Some(&address[index + 1..])),
+                None => (address, None),
+            }
+        };
+        if host.is_empty() {
+            return None;
+        }
+        let port = match port {
+            Some(port) => port.parse().ok()?,
+            None => DEFAULT_PORT,
+        };
+        Some((host.to_owned(), port))
+    }
+
+    pub fn encryption(&self) -> Encryption {
+        self.encryption
+    }
+
+    /// The `host:port` of the server, IPv6 hosts are enclosed in brackets
+    pub fn address(&self) -> String {
+        if self.host.contains(':') {
+            format!("[{}]:{}", self.host, self.port)
+        } else {
+            format!("{}:{}", self.host, self.port)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_parse_schemes() {
+        let cases = vec![
+            ("bolt://localhost:7687", false, Encryption::None),
+            ("bolt+s://localhost:7687", false, Encryption::Verified),
+            ("bolt+ssc://localhost:7687", false, Encryption::SelfSigned),
+            ("neo4j://localhost:7687", true, Encryption::None),
+            ("neo4j+s://localhost:7687", true, Encryption::Verified),
+            ("neo4j+ssc://localhost:7687", true, Encryption::SelfSigned),
+        ];
+        for (uri, routing, encryption) in cases {
+            let uri = Uri::parse(uri).unwrap();
+            assert_eq!(uri.routing, routing);
+            assert_eq!(uri.encryption(), encryption);
+            assert_eq!(uri.host, "localhost");
+            assert_eq!(uri.port, 7687);
+        }
+    }
+
+    #[test]
+    fn should_accept_bare_addresses() {
+        let uri = Uri::parse("127.0.0.1:7688").unwrap();
+        assert!(!uri.routing);
+        assert_eq!(uri.encryption(), Encryption::None);
+        assert_eq!(uri.host, "127.0.0.1");
+        assert_eq!(uri.port, 7688);
+    }
+
+    #[test]
+    fn should_default_the_port() {
+        let uri = Uri::parse("neo4j+s://abc.databases.neo4j.io").unwrap();
+        assert_eq!(uri.host, "abc.databases.neo4j.io");
+        assert_eq!(uri.port, 7687);
+
+        let uri = Uri::parse("bolt://[::1]/").unwrap();
+        assert_eq!(uri.host, "::1");
+        assert_eq!(uri.port, 7687);
+    }
+
+    #[test]
+    fn should_parse_ipv6_addresses() {
+        let uri = Uri::parse("bolt://[::1]:7688").unwrap();
+        assert_eq!(uri.host, "::1");
+        assert_eq!(uri.port, 7688);
+        assert_eq!(uri.address(), "[::1]:7688");
+    }
+
+    #[test]
+    fn should_parse_the_routing_context() {
+        let uri = Uri::parse("neo4j://localhost:7687?region=eu&policy=fast").unwrap();
+        assert_eq!(
+            uri.routing_context,
+            vec![
+                ("region".to_owned(), "eu".to_owned()),
+                ("policy".to_owned(), "fast".to_owned())
+            ]
+        );
+    }
+
+    #[test]
+    fn should_reject_invalid_uris() {
+        assert!(matches!(
+            Uri::parse("http://localhost:7687"),
+            Err(Error::UnsupportedScheme(_))
+        ));
+        assert!(Uri::parse("bolt://localhost:port").is_err());
+        assert!(Uri::parse("bolt://:7687").is_err());
+        assert!(Uri::parse("bolt://localhost:7687?region=eu").is_err());
+        assert!(Uri::parse("neo4j://localhost:7687?region").is_err());
+    }
+}
@@ -3,16 +3,54 @@ use bytes::{BufMut, Bytes, BytesMut};
 use std::cmp::PartialEq;
 use std::fmt::Debug;
 
-#[derive(Debug, PartialEq, Clone, Copy)]
-pub enum Version {
-    V4_1,
+/// A bolt protocol version supported by the driver, ordered from oldest to newest, together with
+/// the patches the server agreed to in HELLO.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+pub struct Version {
+    protocol: Protocol,
+    utc_patch: bool,
+}
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+enum Protocol {
     V4,
+    V4_1,
+    V4_2,
+    V4_3,
+    V4_4,
+    V5_0,
+    V5_1,
+    V5_2,
+    V5_3,
+    V5_4,
 }
 
 impl Version {
+    pub const V4: Version = Version::new(Protocol::V4);
+    pub const V4_1: Version = Version::new(Protocol::V4_1);
+    pub const V4_2: Version = Version::new(Protocol::V4_2);
+    pub const V4_3: Version = Version::new(Protocol::V4_3);
+    pub const V4_4: Version = Version::new(Protocol::V4_4);
+    pub const V5_0: Version = Version::new(Protocol::V5_0);
+    pub const V5_1: Version = Version::new(Protocol::V5_1);
+    pub const V5_2: Version = Version::new(Protocol::V5_2);
+    pub const V5_3: Version = Version::new(Protocol::V5_3);
+    pub const V5_4: Version = Version::new(Protocol::V5_4);
+
+    const fn new(protocol: Protocol) -> Version {
+        Version {
+            protocol,
+            utc_patch: false,
+        }
+    }
+
+    /// The versions offered to the server during the handshake, newest first.
+    ///
+    /// Each entry is encoded as `[0, range, minor, major]`, where `range` is the number of
+    /// consecutive minor versions below `minor` that are also acceptable.
     pub fn supported_versions() -> Bytes {
         let mut bytes = BytesMut::with_capacity(16);
-        let versions: [u32; 4] = [0x0104, 0x0004, 0, 0];
+        let versions: [u32; 4] = [0x0004_0405, 0x0002_0404, 0x0104, 0x0004];
         for version in versions.iter() {
             bytes.put_u32(*version);
         }
@@ -20,15 +58,75 @@ impl Version {
     }
 
     pub fn parse(version_bytes: [u8; 4]) -> Result<Version> {
-        match u32::from_be_bytes(version_bytes) {
-            260 => Ok(Version::V4_1),
-            4 => Ok(Version::V4),
-            v => Err(Error::UnsupportedVersion(format!(
+        match version_bytes {
+            [0, 0, 0, 4] => Ok(Version::V4),
+            [0, 0, 1, 4] => Ok(Version::V4_1),
+            [0, 0, 2, 4] => Ok(Version::V4_2),
+            [0, 0, 3, 4] => Ok(Version::V4_3),
+            [0, 0, 4, 4] => Ok(Version::V4_4),
+            [0, 0, 0, 5] => Ok(Version::V5_0),
+            [0, 0, 1, 5] => Ok(Version::V5_1),
+            [0, 0, 2, 5] => Ok(Version::V5_2),
+            [0, 0, 3, 5] => Ok(Version::V5_3),
+            [0, 0, 4, 5] => Ok(Version::V5_4),
+            _ => Err(Error::UnsupportedVersion(format!(
                 "version {} is not supported",
-                v
+                u32::from_be_bytes(version_bytes)
             ))),
         }
     }
+
+    /// Whether routing tables are fetched with the ROUTE message (Bolt 4.3+)
+    pub fn supports_route(&self) -> bool {
+        *self >= Version::V4_3
+    }
+
+    /// Whether RUN and BEGIN accept the `imp_user` extra (Bolt 4.4+)
+    pub fn supports_impersonation(&self) -> bool {
+        *self >= Version::V4_4
+    }
+
+    /// Whether graph entities carry string element ids next to the legacy integer ids (Bolt 5.0+)
+    pub fn supports_element_ids(&self) -> bool {
+        *self >= Version::V5_0
+    }
+
+    /// Whether datetimes are sent as UTC instants instead of local seconds (Bolt 5.0+, or 4.3
+    /// and 4.4 with the `utc` patch)
+    pub fn uses_utc_datetime(&self) -> bool {
+        *self >= Version::V5_0 || self.utc_patch
+    }
+
+    /// Whether HELLO offers the `utc` patch with the `patch_bolt` hint (Bolt 4.3 and 4.4)
+    pub fn supports_utc_patch(&self) -> bool {
+        *self >= Version::V4_3 && *self < Version::V5_0
+    }
+
+    /// The same version with the `utc` patch the server acknowledged in its HELLO response
+    pub fn with_utc_patch(self) -> Version {
+        Version {
+            utc_patch: self.supports_utc_patch(),
+            ..self
+        }
+    }
+
+    /// Whether authentication happens with LOGON after HELLO instead of within it (Bolt 5.1+)
+    pub fn supports_logon(&self) -> bool {
+        *self >= Version::V5_1
+    }
+
+    /// Whether HELLO must describe the driver with a `bolt_agent` map (Bolt 5.3+)
+    pub fn requires_bolt_agent(&self) -> bool {
+        *self >= Version::V5_3
+    }
+}
+
+/// The version as `major.minor`, e.g. `4.4`
+impl std::fmt::Display for Version {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = format!("{:?}", self.protocol);
+        f.write_str(&name[1..].replace('_', "."))
+    }
 }
 
 #[cfg(test)]
@@ -37,7 +135,57 @@ mod tests {
 
     #[tokio::test]
     async fn should_parse_version() {
-        assert_eq!(Version::parse([0, 0, 1, 4]).unwrap(), Version::V4_1);
         assert_eq!(Version::parse([0, 0, 0, 4]).unwrap(), Version::V4);
+        assert_eq!(Version::parse([0, 0, 1, 4]).unwrap(), Version::V4_1);
+        assert_eq!(Version::parse([0, 0, 2, 4]).unwrap(), Version::V4_2);
+        assert_eq!(Version::parse([0, 0, 3, 4]).unwrap(), Version::V4_3);
+        assert_eq!(Version::parse([0, 0, 4, 4]).unwrap(), Version::V4_4);
+        assert_eq!(Version::parse([0, 0, 0, 5]).unwrap(), Version::V5_0);
+        assert_eq!(Version::parse([0, 0, 4, 5]).unwrap(), Version::V5_4);
+        assert!(Version::parse([0, 0, 0, 3]).is_err());
+        assert!(Version::parse([0, 2, 4, 4]).is_err());
+    }
+
+    #[test]
+    fn should_offer_version_ranges() {
+        assert_eq!(
+            Version::supported_versions(),
+            Bytes::from_static(&[0, 4, 4, 5, 0, 2, 4, 4, 0, 0, 1, 4, 0, 0, 0, 4])
+        );
+    }
+
+    #[test]
+    fn should_order_versions() {
+        assert!(Version::V4 < Version::V4_1);
+        assert!(Version::V4_3 < Version::V4_4);
+        assert!(!Version::V4_2.supports_route());
+        assert!(Version::V4_3.supports_route());
+        assert!(!Version::V4_3.supports_impersonation());
+        assert!(Version::V4_4.supports_impersonation());
+        assert!(Version::V4_4 < Version::V5_0);
+        assert!(!Version::V4_4.supports_element_ids());
+        assert!(Version::V5_0.supports_element_ids());
+        assert!(!Version::V5_0.supports_logon());
+        assert!(Version::V5_1.supports_logon());
+    }
+
+    #[test]
+    fn should_apply_the_utc_patch_to_4_3_and_4_4() {
+        assert!(!Version::V4_2.supports_utc_patch());
+        assert!(!Version::V4_2.with_utc_patch().uses_utc_datetime());
+        assert!(!Version::V4_4.uses_utc_datetime());
+        assert!(Version::V4_3.with_utc_patch().uses_utc_datetime());
+        assert!(Version::V4_4.with_utc_patch().uses_utc_datetime());
+        assert!(Version::V4_4.with_utc_patch() < Version::V5_0);
+        assert!(Version::V4_4.with_utc_patch().supports_impersonation());
+        assert!(!Version::V5_0.supports_utc_patch());
+        assert!(Version::V5_0.uses_utc_datetime());
+    }
+
+    #[test]
+    fn should_display_the_version_number() {
+        assert_eq!(Version::V4.to_string(), "4");
+        assert_eq!(Version::V4_4.with_utc_patch().to_string(), "4.4");
+        assert_eq!(Version::V5_4.to_string(), "5.4");
     }
 }
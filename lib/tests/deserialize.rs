@@ -0,0 +1,63 @@
+use neo4rs::*;
+use serde::Deserialize;
+
+mod container;
+
+#[derive(Debug, PartialEq, Deserialize)]
+struct Person {
+    name: String,
+    born: chrono::NaiveDate,
+    nickname: Option<String>,
+}
+
+#[derive(Debug, PartialEq, Deserialize)]
+struct Knows {
+    since: i64,
+}
+
+#[derive(Debug, PartialEq, Deserialize)]
+struct Friendship {
+    p: Person,
+    r: Knows,
+    friends: Vec<String>,
+}
+
+#[tokio::test]
+async fn deserialize() {
+    let neo4j = container::Neo4jContainer::new().await;
+    let graph = neo4j.graph();
+
+    let mut result = graph
+        .execute(query(
+            "CREATE (p:Person { name: 'Mark', born: date('1990-05-12') })
+                    -[r:KNOWS { since: 2020 }]->(:Person { name: 'Jake', born: date('1991-01-01') })
+             RETURN p, r, ['Jake'] AS friends",
+        ))
+        .await
+        .unwrap();
+    let row = result.next().await.unwrap().unwrap();
+
+    let mark = Person {
+        name: "Mark".into(),
+        born: chrono::NaiveDate::from_ymd_opt(1990, 5, 12).unwrap(),
+        nickname: None,
+    };
+    assert_eq!(row.get::<Node>("p").unwrap().to::<Person>().unwrap(), mark);
+    assert_eq!(
+        row.get::<Relation>("r").unwrap().to::<Knows>().unwrap(),
+        Knows { since: 2020 }
+    );
+    assert_eq!(
+        row.to::<Friendship>().unwrap(),
+        Friendship {
+            p: mark,
+            r: Knows { since: 2020 },
+            friends: vec!["Jake".into()],
+        }
+    );
+
+    match row.to::<Knows>() {
+        Err(Error::Deserialize(e)) => assert_eq!(e.to_string(), "missing field `since`"),
+        other => panic!("unexpected {:?}", other),
+    }
+}
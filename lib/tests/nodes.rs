@@ -24,5 +24,6 @@ async fn nodes() {
         assert_eq!(name, "Mr Mark");
         assert_eq!(labels, vec!["Person"]);
         assert!(id >= 0);
+        assert!(!node.element_id().is_empty());
     }
 }
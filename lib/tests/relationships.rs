@@ -16,6 +16,9 @@ async fn relationships() {
     assert!(relation.id() > -1);
     assert!(relation.start_node_id() > -1);
     assert!(relation.end_node_id() > -1);
+    assert!(!relation.element_id().is_empty());
+    assert!(!relation.start_node_element_id().is_empty());
+    assert!(!relation.end_node_element_id().is_empty());
     assert_eq!(relation.typ(), "WORKS_AT");
     assert_eq!(relation.get::<String>("as").unwrap(), "Engineer");
 }
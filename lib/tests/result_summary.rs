@@ -0,0 +1,34 @@
+use neo4rs::*;
+use uuid::Uuid;
+
+mod container;
+
+#[tokio::test]
+async fn result_summary() {
+    let neo4j = container::Neo4jContainer::new().await;
+    let graph = neo4j.graph();
+
+    let id = Uuid::new_v4().to_string();
+    let summary = graph
+        .run_with_summary(
+            query("CREATE (p:Person {id: $id})-[:KNOWS]->(:Person {id: $id})")
+                .param("id", id.clone()),
+        )
+        .await
+        .unwrap();
+    assert_eq!(summary.counters.nodes_created, 2);
+    assert_eq!(summary.counters.relationships_created, 1);
+    assert_eq!(summary.counters.properties_set, 2);
+    assert_eq!(summary.counters.labels_added, 2);
+    assert!(summary.counters.contains_updates);
+    assert_eq!(summary.query_type, Some(QueryType::WriteOnly));
+
+    let mut result = graph
+        .execute(query("MATCH (p:Person {id: $id}) RETURN p").param("id", id))
+        .await
+        .unwrap();
+    assert!(result.next().await.unwrap().is_some());
+    let summary = result.finish().await.unwrap();
+    assert_eq!(summary.counters, Counters::default());
+    assert_eq!(summary.query_type, Some(QueryType::ReadOnly));
+}
@@ -0,0 +1,58 @@
+use futures::TryStreamExt;
+use neo4rs::*;
+use std::convert::TryFrom;
+
+mod container;
+
+struct Person {
+    name: String,
+}
+
+impl TryFrom<Row> for Person {
+    type Error = Error;
+
+    fn try_from(row: Row) -> Result<Self> {
+        let name = row.get("name").ok_or(Error::ConversionError)?;
+        Ok(Person { name })
+    }
+}
+
+#[tokio::test]
+async fn row_stream() {
+    let neo4j = container::Neo4jContainer::new().await;
+    let graph = neo4j.graph();
+
+    let rows: Vec<Row> = graph
+        .execute(query("UNWIND range(1, 10) AS n RETURN n"))
+        .await
+        .unwrap()
+        .into_stream()
+        .try_collect()
+        .await
+        .unwrap();
+    assert_eq!(rows.len(), 10);
+
+    let even: Vec<Row> = graph
+        .execute(query("UNWIND range(1, 10) AS n RETURN n"))
+        .await
+        .unwrap()
+        .into_stream()
+        .try_filter(|row| futures::future::ready(row.get::<i64>("n").unwrap() % 2 == 0))
+        .try_collect()
+        .await
+        .unwrap();
+    assert_eq!(even.len(), 5);
+
+    let handle = tokio::spawn(async move {
+        graph
+            .execute(query("UNWIND ['Mark', 'Jake'] AS name RETURN name"))
+            .await
+            .unwrap()
+            .into_stream_as::<Person>()
+            .map_ok(|person| person.name)
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap()
+    });
+    assert_eq!(handle.await.unwrap(), vec!["Mark", "Jake"]);
+}
@@ -0,0 +1,74 @@
+use neo4rs::*;
+use serde::{Deserialize, Serialize};
+
+mod container;
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Person {
+    name: String,
+    #[serde(with = "neo4rs::temporal::date")]
+    born: chrono::NaiveDate,
+    nickname: Option<String>,
+}
+
+#[derive(Serialize)]
+struct Params {
+    group: &'static str,
+    limit: i64,
+}
+
+#[tokio::test]
+async fn serialize_params() {
+    let neo4j = container::Neo4jContainer::new().await;
+    let graph = neo4j.graph();
+
+    let people = vec![
+        Person {
+            name: "Mark".into(),
+            born: chrono::NaiveDate::from_ymd_opt(1990, 5, 12).unwrap(),
+            nickname: None,
+        },
+        Person {
+            name: "Jake".into(),
+            born: chrono::NaiveDate::from_ymd_opt(1991, 1, 1).unwrap(),
+            nickname: Some("J".into()),
+        },
+    ];
+
+    graph
+        .run(
+            query("UNWIND $rows AS row CREATE (p:Person:Serialized) SET p = row")
+                .param("rows", &people),
+        )
+        .await
+        .unwrap();
+
+    let mut result = graph
+        .execute(
+            query(
+                "MATCH (p:Serialized) WHERE date.truncate('year', p.born) >= date($group)
+                 RETURN p, p.born AS born ORDER BY p.name LIMIT $limit",
+            )
+            .params(&Params {
+                group: "1990-01-01",
+                limit: 10,
+            }),
+        )
+        .await
+        .unwrap();
+
+    let mut found = Vec::new();
+    while let Ok(Some(row)) = result.next().await {
+        assert!(row.get::<chrono::NaiveDate>("born").is_some());
+        found.push(row.get::<Node>("p").unwrap().to::<Person>().unwrap());
+    }
+    assert_eq!(found.len(), 2);
+    assert_eq!(found[0], people[1]);
+    assert_eq!(found[1], people[0]);
+
+    let error = graph
+        .run(query("RETURN $n").param("n", &vec![u64::MAX]))
+        .await
+        .unwrap_err();
+    assert!(matches!(error, Error::SerializationError(_)));
+}
@@ -0,0 +1,29 @@
+use neo4rs::*;
+
+mod container;
+
+#[tokio::test]
+async fn server_errors() {
+    let neo4j = container::Neo4jContainer::new().await;
+    let graph = neo4j.graph();
+
+    let error = match graph.execute(query("RETRUN 1")).await {
+        Err(Error::Neo4jError(e)) => e,
+        _ => panic!("expected a server error"),
+    };
+    assert_eq!(error.code, "Neo.ClientError.Statement.SyntaxError");
+    assert_eq!(error.category, "Statement");
+    assert!(error.is_client_error());
+    assert!(!error.is_transient());
+
+    let mut result = graph
+        .execute(query("UNWIND [1, 0] AS x RETURN 1 / x"))
+        .await
+        .unwrap();
+    assert!(result.next().await.unwrap().is_some());
+    let error = result.next().await.unwrap_err();
+    assert_eq!(
+        error.as_neo4j_error().unwrap().code,
+        "Neo.ClientError.Statement.ArithmeticError"
+    );
+}